@@ -0,0 +1,58 @@
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+use aoc_2025::algebra::algebra::Matrix;
+
+const ROUNDS: u32 = 20;
+
+fn main() {
+    bench_square::<136>();
+    bench_square::<256>();
+}
+
+fn bench_square<const N: usize>() {
+    let a = filled::<N>(3);
+    let b = filled::<N>(7);
+
+    println!("{N}x{N}");
+    report("naive ijk", time(|| naive_mul(&a, &b)));
+    report("&a * &b", time(|| &a * &b));
+    report("mul_widening i64", time(|| a.mul_widening::<N, i64>(&b)));
+    report("a *= &b", time(|| {
+        let mut m = a.clone();
+        m *= &b;
+        m
+    }));
+}
+
+fn filled<const N: usize>(seed: i32) -> Matrix<N, N, i32> {
+    Matrix {
+        rows: std::array::from_fn(|y| std::array::from_fn(|x| ((x * 31 + y * 17) as i32 * seed) % 7 - 3)),
+    }
+}
+
+/// the multiplication as it used to be, striding down the columns of `rhs`
+#[allow(clippy::needless_range_loop)]
+fn naive_mul<const N: usize>(lhs: &Matrix<N, N, i32>, rhs: &Matrix<N, N, i32>) -> Matrix<N, N, i32> {
+    let mut result = [[0; N]; N];
+    for ry in 0..N {
+        for rx in 0..N {
+            for x in 0..N {
+                result[ry][rx] += *lhs.get(x, ry) * *rhs.get(rx, x);
+            }
+        }
+    }
+    Matrix { rows: result }
+}
+
+fn time<R>(f: impl Fn() -> R) -> Duration {
+    black_box(f());
+    let now = Instant::now();
+    for _ in 0..ROUNDS {
+        black_box(f());
+    }
+    now.elapsed() / ROUNDS
+}
+
+fn report(name: &str, elapsed: Duration) {
+    println!("  {:<18} {:>10.2?}", name, elapsed);
+}
@@ -0,0 +1,57 @@
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+use aoc_2025::combinatorics::combinatorics::RangeSet;
+
+const ROUNDS: u32 = 10;
+
+fn main() {
+    bench_ranges(1_000);
+    bench_ranges(20_000);
+    bench_ranges(100_000);
+}
+
+fn bench_ranges(n: usize) {
+    let ranges = generated_ranges(n, 1);
+    let mut queries = generated_ranges(n, 2).into_iter().map(|r| *r.start()).collect::<Vec<_>>();
+    queries.sort_unstable();
+    let set = RangeSet::from_vec(ranges.clone());
+
+    println!("{n} ranges, {} merged", set.data.len());
+    report("add one by one", time(|| {
+        let mut set = RangeSet::new();
+        for range in ranges.iter().cloned() {
+            set.add(range);
+        }
+        set
+    }));
+    report("from_vec", time(|| RangeSet::from_vec(ranges.clone())));
+    report("contains each", time(|| queries.iter().filter(|&&q| set.contains(q)).count()));
+    report("contains_sorted_batch", time(|| set.contains_sorted_batch(&queries)));
+}
+
+/// puzzle-sized ranges spread over a wide domain, so a good part of them stay disjoint
+fn generated_ranges(n: usize, seed: u64) -> Vec<std::ops::RangeInclusive<u64>> {
+    let mut state = seed;
+    let mut next = move || {
+        state = state.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+        state >> 20
+    };
+
+    (0..n).map(|_| {
+        let lo = next() % 1_000_000_000_000;
+        lo..=lo + next() % 5_000_000
+    }).collect()
+}
+
+fn time<R>(f: impl Fn() -> R) -> Duration {
+    black_box(f());
+    let now = Instant::now();
+    for _ in 0..ROUNDS {
+        black_box(f());
+    }
+    now.elapsed() / ROUNDS
+}
+
+fn report(name: &str, elapsed: Duration) {
+    println!("  {:<22} {:>10.2?}", name, elapsed);
+}
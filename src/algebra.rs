@@ -1,6 +1,9 @@
 pub mod algebra {
+    use std::cmp::{max, min, Ordering};
     use std::fmt::{Display, Formatter};
+    use std::hash::{Hash, Hasher};
     use std::ops::*;
+    use std::str::FromStr;
 
 
     pub trait One {
@@ -42,15 +45,250 @@ pub mod algebra {
     zero_one!(f32);
     zero_one!(f64);
 
+    /// Addition and multiplication with their identities. The `checked_*` variants report overflow
+    /// as `None`, by default they never fail, which is right for floats and types that can't overflow.
+    pub trait Semiring: Sized + Zero + One + Add<Output = Self> + Mul<Output = Self> {
+        fn checked_add(self, rhs: Self) -> Option<Self> {
+            Some(self + rhs)
+        }
+
+        fn checked_mul(self, rhs: Self) -> Option<Self> {
+            Some(self * rhs)
+        }
+    }
+
+    /// A `Semiring` with subtraction. Unsigned integers count too, their subtraction is just partial.
+    pub trait Ring: Semiring + Sub<Output = Self> {
+        fn checked_sub(self, rhs: Self) -> Option<Self> {
+            Some(self - rhs)
+        }
+
+        fn checked_neg(self) -> Option<Self> {
+            Some(Self::zero() - self)
+        }
+    }
+
+    /// Scalars with exact or approximate division, as required by Gaussian elimination.
+    pub trait Field: Ring + Copy + Div<Output = Self> {
+        /// whether the value counts as zero, floats allow for rounding error
+        fn is_zero(&self) -> bool;
+
+        /// whether `self` is a better pivot than `other`, exact types just take the first non-zero entry
+        fn better_pivot(&self, _other: &Self) -> bool {
+            false
+        }
+    }
+
+    /// Integers with the primitive types' truncating division, so `-7 / 2 == -3` and `-7 % 2 == -1`,
+    /// plus their overflow handling variants. Use `util::posmod` for a non-negative remainder.
+    pub trait Integer: Ring + Ord + Div<Output = Self> + Rem<Output = Self> {
+        fn checked_div(self, rhs: Self) -> Option<Self>;
+        fn checked_rem(self, rhs: Self) -> Option<Self>;
+
+        fn wrapping_add(self, rhs: Self) -> Self;
+        fn wrapping_sub(self, rhs: Self) -> Self;
+        fn wrapping_mul(self, rhs: Self) -> Self;
+
+        fn saturating_add(self, rhs: Self) -> Self;
+        fn saturating_sub(self, rhs: Self) -> Self;
+        fn saturating_mul(self, rhs: Self) -> Self;
+    }
+
+    /// `a + b` that panics on overflow in release builds too when the `strict-overflow` feature is on,
+    /// used by the `Matrix` operators so answers can be validated without a debug build.
+    #[inline]
+    pub fn strict_add<T: Semiring>(a: T, b: T) -> T {
+        #[cfg(feature = "strict-overflow")]
+        { a.checked_add(b).expect("attempt to add with overflow") }
+        #[cfg(not(feature = "strict-overflow"))]
+        { a + b }
+    }
+
+    /// `a * b`, see `strict_add`
+    #[inline]
+    pub fn strict_mul<T: Semiring>(a: T, b: T) -> T {
+        #[cfg(feature = "strict-overflow")]
+        { a.checked_mul(b).expect("attempt to multiply with overflow") }
+        #[cfg(not(feature = "strict-overflow"))]
+        { a * b }
+    }
+
+    /// `a - b`, see `strict_add`
+    #[inline]
+    pub fn strict_sub<T: Ring>(a: T, b: T) -> T {
+        #[cfg(feature = "strict-overflow")]
+        { a.checked_sub(b).expect("attempt to subtract with overflow") }
+        #[cfg(not(feature = "strict-overflow"))]
+        { a - b }
+    }
+
+    /// `-a`, see `strict_add`
+    #[inline]
+    pub fn strict_neg<T: Ring + Neg<Output = T>>(a: T) -> T {
+        #[cfg(feature = "strict-overflow")]
+        { a.checked_neg().expect("attempt to negate with overflow") }
+        #[cfg(not(feature = "strict-overflow"))]
+        { -a }
+    }
+
+    macro_rules! integer {
+        ($ty:ty) => {
+            impl Semiring for $ty {
+                fn checked_add(self, rhs: Self) -> Option<Self> {
+                    <$ty>::checked_add(self, rhs)
+                }
+                fn checked_mul(self, rhs: Self) -> Option<Self> {
+                    <$ty>::checked_mul(self, rhs)
+                }
+            }
+            impl Ring for $ty {
+                fn checked_sub(self, rhs: Self) -> Option<Self> {
+                    <$ty>::checked_sub(self, rhs)
+                }
+                fn checked_neg(self) -> Option<Self> {
+                    <$ty>::checked_neg(self)
+                }
+            }
+            impl Integer for $ty {
+                fn checked_div(self, rhs: Self) -> Option<Self> {
+                    <$ty>::checked_div(self, rhs)
+                }
+                fn checked_rem(self, rhs: Self) -> Option<Self> {
+                    <$ty>::checked_rem(self, rhs)
+                }
+                fn wrapping_add(self, rhs: Self) -> Self {
+                    <$ty>::wrapping_add(self, rhs)
+                }
+                fn wrapping_sub(self, rhs: Self) -> Self {
+                    <$ty>::wrapping_sub(self, rhs)
+                }
+                fn wrapping_mul(self, rhs: Self) -> Self {
+                    <$ty>::wrapping_mul(self, rhs)
+                }
+                fn saturating_add(self, rhs: Self) -> Self {
+                    <$ty>::saturating_add(self, rhs)
+                }
+                fn saturating_sub(self, rhs: Self) -> Self {
+                    <$ty>::saturating_sub(self, rhs)
+                }
+                fn saturating_mul(self, rhs: Self) -> Self {
+                    <$ty>::saturating_mul(self, rhs)
+                }
+            }
+        };
+    }
+
+    integer!(u8);
+    integer!(u16);
+    integer!(u32);
+    integer!(u64);
+    integer!(u128);
+    integer!(usize);
+
+    integer!(i8);
+    integer!(i16);
+    integer!(i32);
+    integer!(i64);
+    integer!(i128);
+    integer!(isize);
+
+    macro_rules! float {
+        ($ty:ty, $eps:expr) => {
+            impl Semiring for $ty {}
+            impl Ring for $ty {}
+            impl Field for $ty {
+                fn is_zero(&self) -> bool {
+                    self.abs() < $eps
+                }
+
+                fn better_pivot(&self, other: &Self) -> bool {
+                    self.abs() > other.abs()
+                }
+            }
+        };
+    }
+
+    float!(f32, 1e-5);
+    float!(f64, 1e-9);
+
+    #[derive(Clone, PartialEq, Eq, Hash, Debug)]
     pub struct Matrix<const WIDTH: usize, const HEIGHT: usize, T> {
         pub rows: [[T; WIDTH]; HEIGHT],
     }
 
+    impl<const WIDTH: usize, const HEIGHT: usize, T: Default> Default for Matrix<WIDTH, HEIGHT, T> {
+        fn default() -> Self {
+            Matrix {
+                rows: std::array::from_fn(|_| std::array::from_fn(|_| T::default())),
+            }
+        }
+    }
+
+    /// indexed by `(x, y)`, like `get`
+    impl<const WIDTH: usize, const HEIGHT: usize, T> Index<(usize, usize)> for Matrix<WIDTH, HEIGHT, T> {
+        type Output = T;
+
+        fn index(&self, (x, y): (usize, usize)) -> &T {
+            &self.rows[y][x]
+        }
+    }
+
+    impl<const WIDTH: usize, const HEIGHT: usize, T> IndexMut<(usize, usize)> for Matrix<WIDTH, HEIGHT, T> {
+        fn index_mut(&mut self, (x, y): (usize, usize)) -> &mut T {
+            &mut self.rows[y][x]
+        }
+    }
+
+    const NEIGHBOURS_4: [(isize, isize); 4] = [(0, -1), (-1, 0), (1, 0), (0, 1)];
+    const NEIGHBOURS_8: [(isize, isize); 8] = [(-1, -1), (0, -1), (1, -1), (-1, 0), (1, 0), (-1, 1), (0, 1), (1, 1)];
+
     impl<const WIDTH: usize, const HEIGHT: usize, T> Matrix<WIDTH, HEIGHT, T> {
         pub fn get(&self, x: usize, y: usize) -> &T {
             &self.rows[y][x]
         }
 
+        pub fn get_mut(&mut self, x: usize, y: usize) -> &mut T {
+            &mut self.rows[y][x]
+        }
+
+        pub fn iter_rows(&self) -> impl Iterator<Item = &[T; WIDTH]> {
+            self.rows.iter()
+        }
+
+        /// one iterator per column, each going top to bottom
+        pub fn iter_cols(&self) -> impl Iterator<Item = impl Iterator<Item = &T>> {
+            (0..WIDTH).map(move |x| self.rows.iter().map(move |row| &row[x]))
+        }
+
+        /// every entry as `(x, y, value)`, row by row
+        pub fn iter_positions(&self) -> impl Iterator<Item = (usize, usize, &T)> {
+            self.rows.iter()
+                .enumerate()
+                .flat_map(|(y, row)| row.iter().enumerate().map(move |(x, v)| (x, y, v)))
+        }
+
+        /// orthogonal neighbours of `(x, y)` that lie inside the matrix
+        pub fn neighbours4(&self, x: usize, y: usize) -> impl Iterator<Item = (usize, usize)> + use<WIDTH, HEIGHT, T> {
+            Self::neighbours(x, y, &NEIGHBOURS_4)
+        }
+
+        /// orthogonal and diagonal neighbours of `(x, y)` that lie inside the matrix
+        pub fn neighbours8(&self, x: usize, y: usize) -> impl Iterator<Item = (usize, usize)> + use<WIDTH, HEIGHT, T> {
+            Self::neighbours(x, y, &NEIGHBOURS_8)
+        }
+
+        fn neighbours(
+            x: usize,
+            y: usize,
+            offsets: &'static [(isize, isize)],
+        ) -> impl Iterator<Item = (usize, usize)> + use<WIDTH, HEIGHT, T> {
+            offsets.iter().filter_map(move |&(dx, dy)| {
+                let nx = x.checked_add_signed(dx).filter(|&nx| nx < WIDTH)?;
+                let ny = y.checked_add_signed(dy).filter(|&ny| ny < HEIGHT)?;
+                Some((nx, ny))
+            })
+        }
+
         pub fn map<R: Zero + Copy>(&self, f: fn(&T) -> R) -> Matrix<WIDTH, HEIGHT, R> {
             let mut result = [[R::zero(); WIDTH]; HEIGHT];
             for y in 0..HEIGHT {
@@ -61,6 +299,109 @@ pub mod algebra {
 
             Matrix { rows: result }
         }
+
+        /// folds over all entries, row by row
+        pub fn fold<A>(&self, init: A, f: impl FnMut(A, &T) -> A) -> A {
+            self.rows.iter().flatten().fold(init, f)
+        }
+
+        pub fn count(&self, pred: impl Fn(&T) -> bool) -> usize {
+            self.rows.iter().flatten().filter(|v| pred(v)).count()
+        }
+
+        pub fn zip_with<U, R>(
+            &self,
+            other: &Matrix<WIDTH, HEIGHT, U>,
+            f: impl Fn(&T, &U) -> R,
+        ) -> Matrix<WIDTH, HEIGHT, R> {
+            Matrix {
+                rows: std::array::from_fn(|y| std::array::from_fn(|x| f(self.get(x, y), other.get(x, y)))),
+            }
+        }
+
+        /// `true` wherever `pred` holds
+        pub fn mask(&self, pred: impl Fn(&T) -> bool) -> Matrix<WIDTH, HEIGHT, bool> {
+            Matrix {
+                rows: std::array::from_fn(|y| std::array::from_fn(|x| pred(self.get(x, y)))),
+            }
+        }
+    }
+
+    impl<const WIDTH: usize, const HEIGHT: usize, T: Copy> Matrix<WIDTH, HEIGHT, T> {
+        /// entries of `if_true` where `mask` is set, of `if_false` elsewhere
+        pub fn select(
+            mask: &Matrix<WIDTH, HEIGHT, bool>,
+            if_true: &Matrix<WIDTH, HEIGHT, T>,
+            if_false: &Matrix<WIDTH, HEIGHT, T>,
+        ) -> Matrix<WIDTH, HEIGHT, T> {
+            mask.zip_with(if_true, |&m, &t| (m, t))
+                .zip_with(if_false, |&(m, t), &f| if m { t } else { f })
+        }
+
+        /// keeps the entries where `mask` is set and replaces the rest with `fill`
+        pub fn masked(&self, mask: &Matrix<WIDTH, HEIGHT, bool>, fill: T) -> Matrix<WIDTH, HEIGHT, T> {
+            self.zip_with(mask, |&v, &m| if m { v } else { fill })
+        }
+    }
+
+    impl<const WIDTH: usize, const HEIGHT: usize, T: Copy + PartialOrd> Matrix<WIDTH, HEIGHT, T> {
+        /// position `(x, y)` of the first smallest entry
+        pub fn argmin(&self) -> Option<(usize, usize)> {
+            self.arg_by(|candidate, best| candidate < best)
+        }
+
+        /// position `(x, y)` of the first largest entry
+        pub fn argmax(&self) -> Option<(usize, usize)> {
+            self.arg_by(|candidate, best| candidate > best)
+        }
+
+        fn arg_by(&self, better: impl Fn(&T, &T) -> bool) -> Option<(usize, usize)> {
+            let mut best: Option<(usize, usize)> = None;
+            for y in 0..HEIGHT {
+                for x in 0..WIDTH {
+                    if best.is_none_or(|(bx, by)| better(self.get(x, y), self.get(bx, by))) {
+                        best = Some((x, y));
+                    }
+                }
+            }
+            best
+        }
+
+        pub fn min(&self) -> Option<T> {
+            self.argmin().map(|(x, y)| *self.get(x, y))
+        }
+
+        pub fn max(&self) -> Option<T> {
+            self.argmax().map(|(x, y)| *self.get(x, y))
+        }
+    }
+
+    impl<const WIDTH: usize, const HEIGHT: usize, T: Semiring + Copy> Matrix<WIDTH, HEIGHT, T> {
+        pub fn sum(&self) -> T {
+            self.fold(T::zero(), |acc, &v| strict_add(acc, v))
+        }
+
+        pub fn checked_sum(&self) -> Option<T> {
+            self.fold(Some(T::zero()), |acc, &v| acc?.checked_add(v))
+        }
+
+        pub fn product(&self) -> T {
+            self.fold(T::one(), |acc, &v| strict_mul(acc, v))
+        }
+
+        /// element-wise product
+        pub fn hadamard(&self, other: &Matrix<WIDTH, HEIGHT, T>) -> Matrix<WIDTH, HEIGHT, T> {
+            self.zip_with(other, |&a, &b| strict_mul(a, b))
+        }
+    }
+
+    impl<const WIDTH: usize, const HEIGHT: usize> Matrix<WIDTH, HEIGHT, bool> {
+        /// 1 where the mask is set, 0 elsewhere
+        pub fn indicator<T: Zero + One>(&self) -> Matrix<WIDTH, HEIGHT, T> {
+            Matrix {
+                rows: self.rows.map(|row| row.map(|m| if m { T::one() } else { T::zero() })),
+            }
+        }
     }
 
     impl<const WIDTH: usize, const HEIGHT: usize, T: Copy> Matrix<WIDTH, HEIGHT, T> {
@@ -97,6 +438,229 @@ pub mod algebra {
 
             Matrix { rows }
         }
+
+        /// rotates a quarter turn clockwise
+        pub fn rotate_cw(&self) -> Matrix<HEIGHT, WIDTH, T> {
+            Matrix {
+                rows: std::array::from_fn(|y| std::array::from_fn(|x| *self.get(y, HEIGHT - 1 - x))),
+            }
+        }
+
+        /// rotates a quarter turn counter-clockwise
+        pub fn rotate_ccw(&self) -> Matrix<HEIGHT, WIDTH, T> {
+            Matrix {
+                rows: std::array::from_fn(|y| std::array::from_fn(|x| *self.get(WIDTH - 1 - y, x))),
+            }
+        }
+
+        pub fn rotate_180(&self) -> Matrix<WIDTH, HEIGHT, T> {
+            Matrix {
+                rows: std::array::from_fn(|y| std::array::from_fn(|x| *self.get(WIDTH - 1 - x, HEIGHT - 1 - y))),
+            }
+        }
+
+        /// mirrors left to right
+        pub fn flip_h(&self) -> Matrix<WIDTH, HEIGHT, T> {
+            Matrix {
+                rows: std::array::from_fn(|y| std::array::from_fn(|x| *self.get(WIDTH - 1 - x, y))),
+            }
+        }
+
+        /// mirrors top to bottom
+        pub fn flip_v(&self) -> Matrix<WIDTH, HEIGHT, T> {
+            Matrix {
+                rows: std::array::from_fn(|y| std::array::from_fn(|x| *self.get(x, HEIGHT - 1 - y))),
+            }
+        }
+    }
+
+    impl<const N: usize, T: Copy + PartialEq> Matrix<N, N, T> {
+        /// all distinct results of rotating and flipping, starting with the matrix itself
+        pub fn symmetries(&self) -> impl Iterator<Item = Matrix<N, N, T>> + use<N, T> {
+            let mut result: Vec<Matrix<N, N, T>> = Vec::with_capacity(8);
+            let mut current = self.clone();
+            for _ in 0..4 {
+                for candidate in [current.clone(), current.flip_h()] {
+                    if !result.contains(&candidate) {
+                        result.push(candidate);
+                    }
+                }
+                current = current.rotate_cw();
+            }
+            result.into_iter()
+        }
+    }
+
+    impl<const N: usize, T: PartialEq> Matrix<N, N, T> {
+        pub fn is_symmetric(&self) -> bool {
+            (0..N).all(|y| (0..y).all(|x| self.rows[y][x] == self.rows[x][y]))
+        }
+    }
+
+    impl<const WIDTH: usize, const HEIGHT: usize, T: Copy> Matrix<WIDTH, HEIGHT, T> {
+        /// every entry set to `value`
+        pub fn fill(value: T) -> Matrix<WIDTH, HEIGHT, T> {
+            Matrix { rows: [[value; WIDTH]; HEIGHT] }
+        }
+
+        /// `self` to the left of `other`, `OUT_WIDTH` has to be the sum of both widths
+        pub fn hstack<const OTHER_WIDTH: usize, const OUT_WIDTH: usize>(
+            &self,
+            other: &Matrix<OTHER_WIDTH, HEIGHT, T>,
+        ) -> Matrix<OUT_WIDTH, HEIGHT, T> {
+            const { assert!(OUT_WIDTH == WIDTH + OTHER_WIDTH, "hstack width mismatch") };
+            Matrix {
+                rows: std::array::from_fn(|y| std::array::from_fn(|x| {
+                    if x < WIDTH { self.rows[y][x] } else { other.rows[y][x - WIDTH] }
+                })),
+            }
+        }
+
+        /// `self` on top of `other`, `OUT_HEIGHT` has to be the sum of both heights
+        pub fn vstack<const OTHER_HEIGHT: usize, const OUT_HEIGHT: usize>(
+            &self,
+            other: &Matrix<WIDTH, OTHER_HEIGHT, T>,
+        ) -> Matrix<WIDTH, OUT_HEIGHT, T> {
+            const { assert!(OUT_HEIGHT == HEIGHT + OTHER_HEIGHT, "vstack height mismatch") };
+            Matrix {
+                rows: std::array::from_fn(|y| if y < HEIGHT { self.rows[y] } else { other.rows[y - HEIGHT] }),
+            }
+        }
+
+        /// Keeps the top left corner, cutting off or filling in with `fill` on the right and bottom
+        pub fn resize<const NEW_WIDTH: usize, const NEW_HEIGHT: usize>(
+            &self,
+            fill: T,
+        ) -> Matrix<NEW_WIDTH, NEW_HEIGHT, T> {
+            Matrix {
+                rows: std::array::from_fn(|y| std::array::from_fn(|x| {
+                    if x < WIDTH && y < HEIGHT { self.rows[y][x] } else { fill }
+                })),
+            }
+        }
+
+        /// Places `self` with its top left corner at `(x, y)` in a larger matrix of `fill`
+        pub fn pad<const NEW_WIDTH: usize, const NEW_HEIGHT: usize>(
+            &self,
+            x: usize,
+            y: usize,
+            fill: T,
+        ) -> Matrix<NEW_WIDTH, NEW_HEIGHT, T> {
+            if x + WIDTH > NEW_WIDTH || y + HEIGHT > NEW_HEIGHT {
+                panic!("Padded matrix too small");
+            }
+
+            let mut result = Matrix::fill(fill);
+            for (row, src) in result.rows[y..y + HEIGHT].iter_mut().zip(&self.rows) {
+                row[x..x + WIDTH].copy_from_slice(src);
+            }
+            result
+        }
+    }
+
+    impl<const WIDTH: usize, const HEIGHT: usize, T: Zero + One + Copy> Matrix<WIDTH, HEIGHT, T> {
+        /// 1s on the main diagonal, also for non-square shapes
+        pub fn identity() -> Matrix<WIDTH, HEIGHT, T> {
+            Matrix {
+                rows: std::array::from_fn(|y| std::array::from_fn(|x| if x == y { T::one() } else { T::zero() })),
+            }
+        }
+
+        /// `diag` along the main diagonal starting top left, 0 elsewhere
+        pub fn diagonal(diag: &[T]) -> Matrix<WIDTH, HEIGHT, T> {
+            if diag.len() > WIDTH.min(HEIGHT) {
+                panic!("Diagonal too long");
+            }
+
+            let mut result = Matrix::fill(T::zero());
+            for (i, &v) in diag.iter().enumerate() {
+                result.rows[i][i] = v;
+            }
+            result
+        }
+    }
+
+    impl<const WIDTH: usize, const HEIGHT: usize, T: Semiring + Copy> Matrix<WIDTH, HEIGHT, T> {
+        /// Block matrix with `self[y][x] * other` as block `(x, y)`,
+        /// `OUT_WIDTH`/`OUT_HEIGHT` have to be the products of the dimensions
+        pub fn kronecker<const OW: usize, const OH: usize, const OUT_WIDTH: usize, const OUT_HEIGHT: usize>(
+            &self,
+            other: &Matrix<OW, OH, T>,
+        ) -> Matrix<OUT_WIDTH, OUT_HEIGHT, T> {
+            const { assert!(OUT_WIDTH == WIDTH * OW && OUT_HEIGHT == HEIGHT * OH, "kronecker shape mismatch") };
+            Matrix {
+                rows: std::array::from_fn(|y| std::array::from_fn(|x| {
+                    strict_mul(self.rows[y / OH][x / OW], other.rows[y % OH][x % OW])
+                })),
+            }
+        }
+    }
+
+    impl<const N: usize, T: Semiring + Copy> Matrix<N, N, T> {
+        /// sum of the main diagonal
+        pub fn trace(&self) -> T {
+            (0..N).fold(T::zero(), |acc, i| strict_add(acc, self.rows[i][i]))
+        }
+    }
+
+    /// Borrowed rectangular window into a `Matrix`, coordinates are relative to its top left corner
+    pub struct MatrixView<'a, const WIDTH: usize, const HEIGHT: usize, T> {
+        matrix: &'a Matrix<WIDTH, HEIGHT, T>,
+        x: usize,
+        y: usize,
+        width: usize,
+        height: usize,
+    }
+
+    impl<const WIDTH: usize, const HEIGHT: usize, T> Matrix<WIDTH, HEIGHT, T> {
+        /// the `width` x `height` window with top left corner `(x, y)`
+        pub fn view(&self, x: usize, y: usize, width: usize, height: usize) -> MatrixView<'_, WIDTH, HEIGHT, T> {
+            if x + width > WIDTH || y + height > HEIGHT {
+                panic!("View out of bounds");
+            }
+            MatrixView { matrix: self, x, y, width, height }
+        }
+
+        /// every `width` x `height` window, row by row
+        pub fn windows(&self, width: usize, height: usize) -> impl Iterator<Item = MatrixView<'_, WIDTH, HEIGHT, T>> {
+            let xs = (WIDTH + 1).saturating_sub(width);
+            let ys = (HEIGHT + 1).saturating_sub(height);
+            (0..ys).flat_map(move |y| (0..xs).map(move |x| self.view(x, y, width, height)))
+        }
+    }
+
+    impl<'a, const WIDTH: usize, const HEIGHT: usize, T> MatrixView<'a, WIDTH, HEIGHT, T> {
+        pub fn width(&self) -> usize {
+            self.width
+        }
+
+        pub fn height(&self) -> usize {
+            self.height
+        }
+
+        /// position of the view's top left corner in the underlying matrix
+        pub fn offset(&self) -> (usize, usize) {
+            (self.x, self.y)
+        }
+
+        pub fn get(&self, x: usize, y: usize) -> &'a T {
+            if x >= self.width || y >= self.height {
+                panic!("Index out of view");
+            }
+            self.matrix.get(self.x + x, self.y + y)
+        }
+
+        pub fn iter_rows(&self) -> impl Iterator<Item = &'a [T]> + use<'a, WIDTH, HEIGHT, T> {
+            let (x, width) = (self.x, self.width);
+            self.matrix.rows[self.y..self.y + self.height].iter().map(move |row| &row[x..x + width])
+        }
+
+        /// every entry as `(x, y, value)` relative to the view, row by row
+        pub fn iter_positions(&self) -> impl Iterator<Item = (usize, usize, &'a T)> + use<'a, WIDTH, HEIGHT, T> {
+            self.iter_rows()
+                .enumerate()
+                .flat_map(|(y, row)| row.iter().enumerate().map(move |(x, v)| (x, y, v)))
+        }
     }
 
     impl<const WIDTH: usize, T: Zero + Copy> Matrix<WIDTH, 1, T> {
@@ -129,13 +693,31 @@ pub mod algebra {
     impl<
         const WIDTH: usize,
         const HEIGHT: usize,
-        T: Add<Output = T> + Mul<Output = T> + Copy + Zero,
+        T: Semiring + Copy,
     > Matrix<WIDTH, HEIGHT, T>
     {
+        /// Convolution with the centered `kernel`, treating everything outside the matrix as 0
         pub fn convolve_0<const KW: usize, const KH: usize>(
             &self,
             kernel: &Matrix<KW, KH, T>,
         ) -> Matrix<WIDTH, HEIGHT, T> {
+            self.convolve_with(kernel, |acc, k, m| Some(strict_add(acc, strict_mul(k, m))))
+                .expect("infallible step")
+        }
+
+        /// `convolve_0`, or `None` if any partial sum overflows
+        pub fn checked_convolve<const KW: usize, const KH: usize>(
+            &self,
+            kernel: &Matrix<KW, KH, T>,
+        ) -> Option<Matrix<WIDTH, HEIGHT, T>> {
+            self.convolve_with(kernel, |acc, k, m| acc.checked_add(k.checked_mul(m)?))
+        }
+
+        fn convolve_with<const KW: usize, const KH: usize>(
+            &self,
+            kernel: &Matrix<KW, KH, T>,
+            mut step: impl FnMut(T, T, T) -> Option<T>,
+        ) -> Option<Matrix<WIDTH, HEIGHT, T>> {
             if KW % 2 == 0 {
                 panic!("Kernel must have odd width");
             }
@@ -163,107 +745,2519 @@ pub mod algebra {
 
                             let kval = *kernel.get(kx, ky);
                             let mval = *self.get(mx, my);
-                            result = result + (kval * mval);
+                            result = step(result, kval, mval)?;
                         }
                     }
                     mat_result[y][x] = result;
                 }
             }
 
-            Matrix { rows: mat_result }
+            Some(Matrix { rows: mat_result })
         }
-    }
 
-    impl<const WIDTH: usize, const HEIGHT: usize, T: Add<Output = T> + Copy> Add
-    for Matrix<WIDTH, HEIGHT, T>
-    {
-        type Output = Self;
+        /// element-wise sum, or `None` on overflow
+        pub fn checked_add(&self, other: &Matrix<WIDTH, HEIGHT, T>) -> Option<Matrix<WIDTH, HEIGHT, T>> {
+            let mut rows = self.rows;
+            for (row, other_row) in rows.iter_mut().zip(&other.rows) {
+                for (v, &o) in row.iter_mut().zip(other_row) {
+                    *v = v.checked_add(o)?;
+                }
+            }
 
-        fn add(self, other: Self) -> Self {
-            let mut result = self.rows.clone();
-            for y in 0..HEIGHT {
-                for x in 0..WIDTH {
-                    result[y][x] = result[y][x] + *other.get(x, y);
+            Some(Matrix { rows })
+        }
+
+        /// Matrix product, or `None` if any entry overflows along the way
+        pub fn checked_mul<const OTHER_WIDTH: usize>(
+            &self,
+            rhs: &Matrix<OTHER_WIDTH, WIDTH, T>,
+        ) -> Option<Matrix<OTHER_WIDTH, HEIGHT, T>> {
+            let mut result = [[T::zero(); OTHER_WIDTH]; HEIGHT];
+            for (result_row, row) in result.iter_mut().zip(&self.rows) {
+                for (&a, rhs_row) in row.iter().zip(&rhs.rows) {
+                    for (o, &b) in result_row.iter_mut().zip(rhs_row) {
+                        *o = o.checked_add(a.checked_mul(b)?)?;
+                    }
                 }
             }
-            Matrix { rows: result }
+
+            Some(Matrix { rows: result })
         }
     }
 
-    impl<const WIDTH: usize, const HEIGHT: usize, T: Sub<Output = T> + Copy> Sub
-    for Matrix<WIDTH, HEIGHT, T>
-    {
+    impl<const WIDTH: usize, const HEIGHT: usize, T: Ring + Copy> Matrix<WIDTH, HEIGHT, T> {
+        /// element-wise difference, or `None` on overflow
+        pub fn checked_sub(&self, other: &Matrix<WIDTH, HEIGHT, T>) -> Option<Matrix<WIDTH, HEIGHT, T>> {
+            let mut rows = self.rows;
+            for (row, other_row) in rows.iter_mut().zip(&other.rows) {
+                for (v, &o) in row.iter_mut().zip(other_row) {
+                    *v = v.checked_sub(o)?;
+                }
+            }
+
+            Some(Matrix { rows })
+        }
+    }
+
+    impl<const WIDTH: usize, const HEIGHT: usize, T: Semiring + Copy> AddAssign<&Matrix<WIDTH, HEIGHT, T>>
+    for Matrix<WIDTH, HEIGHT, T>
+    {
+        fn add_assign(&mut self, other: &Self) {
+            for (row, other_row) in self.rows.iter_mut().zip(&other.rows) {
+                for (v, &o) in row.iter_mut().zip(other_row) {
+                    *v = strict_add(*v, o);
+                }
+            }
+        }
+    }
+
+    impl<const WIDTH: usize, const HEIGHT: usize, T: Ring + Copy> SubAssign<&Matrix<WIDTH, HEIGHT, T>>
+    for Matrix<WIDTH, HEIGHT, T>
+    {
+        fn sub_assign(&mut self, other: &Self) {
+            for (row, other_row) in self.rows.iter_mut().zip(&other.rows) {
+                for (v, &o) in row.iter_mut().zip(other_row) {
+                    *v = strict_sub(*v, o);
+                }
+            }
+        }
+    }
+
+    impl<const WIDTH: usize, const HEIGHT: usize, T: Semiring + Copy> AddAssign for Matrix<WIDTH, HEIGHT, T> {
+        fn add_assign(&mut self, other: Self) {
+            *self += &other;
+        }
+    }
+
+    impl<const WIDTH: usize, const HEIGHT: usize, T: Ring + Copy> SubAssign for Matrix<WIDTH, HEIGHT, T> {
+        fn sub_assign(&mut self, other: Self) {
+            *self -= &other;
+        }
+    }
+
+    impl<const WIDTH: usize, const HEIGHT: usize, T: Semiring + Copy> Add
+    for Matrix<WIDTH, HEIGHT, T>
+    {
+        type Output = Self;
+
+        fn add(mut self, other: Self) -> Self {
+            self += &other;
+            self
+        }
+    }
+
+    impl<const WIDTH: usize, const HEIGHT: usize, T: Ring + Copy> Sub
+    for Matrix<WIDTH, HEIGHT, T>
+    {
+        type Output = Self;
+
+        fn sub(mut self, other: Self) -> Self {
+            self -= &other;
+            self
+        }
+    }
+
+    impl<const WIDTH: usize, const HEIGHT: usize, T: Ring + Neg<Output = T> + Copy> Neg for Matrix<WIDTH, HEIGHT, T> {
+        type Output = Self;
+
+        fn neg(self) -> Self {
+            Matrix { rows: self.rows.map(|row| row.map(strict_neg)) }
+        }
+    }
+
+    /// adds the scalar to every entry
+    impl<const WIDTH: usize, const HEIGHT: usize, T: Semiring + Copy> Add<T> for Matrix<WIDTH, HEIGHT, T> {
+        type Output = Self;
+
+        fn add(self, rhs: T) -> Self {
+            Matrix { rows: self.rows.map(|row| row.map(|v| strict_add(v, rhs))) }
+        }
+    }
+
+    /// multiplies every entry with the scalar
+    impl<const WIDTH: usize, const HEIGHT: usize, T: Semiring + Copy> Mul<T> for Matrix<WIDTH, HEIGHT, T> {
+        type Output = Self;
+
+        fn mul(self, rhs: T) -> Self {
+            Matrix { rows: self.rows.map(|row| row.map(|v| strict_mul(v, rhs))) }
+        }
+    }
+
+    impl<const WIDTH: usize, const HEIGHT: usize, T: Copy> Matrix<WIDTH, HEIGHT, T> {
+        /// Matrix product accumulated in the wider type `A`, e.g. `i16` entries summed as `i64`.
+        ///
+        /// Loops in i-k-j order: each entry of `self` gets multiplied into a whole row of `rhs`,
+        /// so both operands and the result are walked row by row instead of striding down columns.
+        pub fn mul_widening<const OTHER_WIDTH: usize, A>(
+            &self,
+            rhs: &Matrix<OTHER_WIDTH, WIDTH, T>,
+        ) -> Matrix<OTHER_WIDTH, HEIGHT, A>
+        where
+            T: Into<A>,
+            A: Semiring + Copy,
+        {
+            let mut result = [[A::zero(); OTHER_WIDTH]; HEIGHT];
+            for (result_row, row) in result.iter_mut().zip(&self.rows) {
+                mul_row_into(row, rhs, result_row);
+            }
+
+            Matrix { rows: result }
+        }
+    }
+
+    /// `out += row * rhs`, the inner loop shared by all the matrix products
+    fn mul_row_into<const WIDTH: usize, const OTHER_WIDTH: usize, T, A>(
+        row: &[T; WIDTH],
+        rhs: &Matrix<OTHER_WIDTH, WIDTH, T>,
+        out: &mut [A; OTHER_WIDTH],
+    ) where
+        T: Into<A> + Copy,
+        A: Semiring + Copy,
+    {
+        for (&a, rhs_row) in row.iter().zip(&rhs.rows) {
+            let a: A = a.into();
+            for (o, &b) in out.iter_mut().zip(rhs_row) {
+                *o = strict_add(*o, strict_mul(a, b.into()));
+            }
+        }
+    }
+
+    impl<
+        const WIDTH: usize,
+        const HEIGHT: usize,
+        const OTHER_WIDTH: usize,
+        T: Semiring + Copy,
+    > Mul<Matrix<OTHER_WIDTH, WIDTH, T>> for Matrix<WIDTH, HEIGHT, T>
+    {
+        type Output = Matrix<OTHER_WIDTH, HEIGHT, T>;
+
+        fn mul(self, rhs: Matrix<OTHER_WIDTH, WIDTH, T>) -> Self::Output {
+            &self * &rhs
+        }
+    }
+
+    impl<
+        const WIDTH: usize,
+        const HEIGHT: usize,
+        const OTHER_WIDTH: usize,
+        T: Semiring + Copy,
+    > Mul<&Matrix<OTHER_WIDTH, WIDTH, T>> for &Matrix<WIDTH, HEIGHT, T>
+    {
+        type Output = Matrix<OTHER_WIDTH, HEIGHT, T>;
+
+        fn mul(self, rhs: &Matrix<OTHER_WIDTH, WIDTH, T>) -> Self::Output {
+            self.mul_widening(rhs)
+        }
+    }
+
+    /// `self = self * rhs` for square matrices, needing only a single row of scratch space
+    impl<const N: usize, T: Semiring + Copy> MulAssign<&Matrix<N, N, T>>
+    for Matrix<N, N, T>
+    {
+        fn mul_assign(&mut self, rhs: &Matrix<N, N, T>) {
+            for row in self.rows.iter_mut() {
+                let mut scratch = [T::zero(); N];
+                mul_row_into(row, rhs, &mut scratch);
+                *row = scratch;
+            }
+        }
+    }
+
+    impl<const N: usize, T: Semiring + Copy> Matrix<N, N, T> {
+        /// `self^exp` by repeated squaring
+        pub fn pow(&self, mut exp: u64) -> Matrix<N, N, T> {
+            let mut result = Matrix::identity();
+            let mut base = Matrix { rows: self.rows };
+
+            while exp > 0 {
+                if exp & 1 == 1 {
+                    result *= &base;
+                }
+                exp >>= 1;
+                if exp > 0 {
+                    base = &base * &base;
+                }
+            }
+
+            result
+        }
+
+        /// `pow`, or `None` if any intermediate product overflows
+        pub fn checked_pow(&self, mut exp: u64) -> Option<Matrix<N, N, T>> {
+            let mut result = Matrix::identity();
+            let mut base = Matrix { rows: self.rows };
+
+            while exp > 0 {
+                if exp & 1 == 1 {
+                    result = result.checked_mul(&base)?;
+                }
+                exp >>= 1;
+                if exp > 0 {
+                    base = base.checked_mul(&base)?;
+                }
+            }
+
+            Some(result)
+        }
+    }
+
+    impl <const WIDTH: usize, const HEIGHT: usize, T: Display> Display for Matrix<WIDTH, HEIGHT, T> {
+        fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+            for y in 0..HEIGHT {
+                for x in 0..HEIGHT {
+                    write!(f, "{:>2}", self.get(x, y))?;
+                }
+                writeln!(f)?;
+            }
+
+            Ok(())
+        }
+    }
+
+    /// Exact fraction `numer / denom`, always reduced and with a positive denominator.
+    ///
+    /// All arithmetic is overflow-checked: the `checked_*` methods return `None`, the operators panic.
+    #[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+    pub struct Rational<T> {
+        numer: T,
+        denom: T,
+    }
+
+    #[derive(Clone, Copy, PartialEq, Eq, Debug)]
+    pub enum ParseRationalError {
+        Numerator,
+        Denominator,
+        ZeroDenominator,
+        Overflow,
+    }
+
+    fn checked_abs<T: Integer + Copy>(val: T) -> Option<T> {
+        if val < T::zero() { val.checked_neg() } else { Some(val) }
+    }
+
+    /// gcd up to sign, without taking absolute values first so `MIN` inputs work too
+    fn signed_gcd<T: Integer + Copy>(mut a: T, mut b: T) -> T {
+        while b != T::zero() {
+            // only `MIN % -1` overflows, and that remainder is 0
+            let r = a.checked_rem(b).unwrap_or(T::zero());
+            a = b;
+            b = r;
+        }
+        a
+    }
+
+    /// non-negative gcd, `None` only if that is `|MIN|`
+    fn checked_gcd<T: Integer + Copy>(a: T, b: T) -> Option<T> {
+        checked_abs(signed_gcd(a, b))
+    }
+
+    /// floor division for a positive divisor, returning quotient and non-negative remainder
+    fn floor_divmod<T: Integer + Copy>(n: T, d: T) -> (T, T) {
+        let q = n.checked_div(d).unwrap();
+        let r = n % d;
+        if r < T::zero() {
+            (q.checked_sub(T::one()).unwrap(), r.checked_add(d).unwrap())
+        } else {
+            (q, r)
+        }
+    }
+
+    impl<T: Integer + Copy> Rational<T> {
+        pub fn new(numer: T, denom: T) -> Rational<T> {
+            if denom == T::zero() {
+                panic!("Zero denominator");
+            }
+            Rational::checked_new(numer, denom).expect("Rational overflow")
+        }
+
+        /// `None` if `denom` is zero or normalising the sign overflows
+        pub fn checked_new(numer: T, denom: T) -> Option<Rational<T>> {
+            if denom == T::zero() {
+                return None;
+            }
+
+            // dividing by the signed gcd can't overflow unless the result isn't representable anyway
+            let g = signed_gcd(numer, denom);
+            let mut numer = numer.checked_div(g)?;
+            let mut denom = denom.checked_div(g)?;
+            if denom < T::zero() {
+                numer = numer.checked_neg()?;
+                denom = denom.checked_neg()?;
+            }
+
+            Some(Rational { numer, denom })
+        }
+
+        pub fn from_integer(val: T) -> Rational<T> {
+            Rational { numer: val, denom: T::one() }
+        }
+
+        pub fn numer(&self) -> T {
+            self.numer
+        }
+
+        pub fn denom(&self) -> T {
+            self.denom
+        }
+
+        pub fn is_integer(&self) -> bool {
+            self.denom == T::one()
+        }
+
+        pub fn floor(&self) -> T {
+            floor_divmod(self.numer, self.denom).0
+        }
+
+        pub fn ceil(&self) -> T {
+            let (q, r) = floor_divmod(self.numer, self.denom);
+            if r == T::zero() { q } else { q.checked_add(T::one()).expect("Rational overflow") }
+        }
+
+        pub fn abs(&self) -> Rational<T> {
+            Rational {
+                numer: checked_abs(self.numer).expect("Rational overflow"),
+                denom: self.denom,
+            }
+        }
+
+        pub fn checked_recip(self) -> Option<Rational<T>> {
+            Rational::checked_new(self.denom, self.numer)
+        }
+
+        pub fn recip(&self) -> Rational<T> {
+            if self.numer == T::zero() {
+                panic!("Division by zero");
+            }
+            self.checked_recip().expect("Rational overflow")
+        }
+
+        /// `None` if the sum, or the lcm of the denominators, doesn't fit.
+        /// Overflowing cross products alone don't make it fail.
+        pub fn checked_add(self, rhs: Rational<T>) -> Option<Rational<T>> {
+            // split off the integer parts, so the cross products only see proper fractions
+            let (lhs_int, lhs_rem) = floor_divmod(self.numer, self.denom);
+            let (rhs_int, rhs_rem) = floor_divmod(rhs.numer, rhs.denom);
+
+            // r1/d1 + r2/d2 = 1 + (r1/d1 - (d2 - r2)/d2), where the difference lies in [-1, 1) and
+            // its numerator over lcm(d1, d2) fits. Reduce it by gcd(t, g) as in Knuth's algorithm.
+            let g = signed_gcd(self.denom, rhs.denom);
+            let (lhs_scale, rhs_scale) = (rhs.denom / g, self.denom / g);
+            let t = lhs_rem.checked_mul(lhs_scale)?.checked_sub((rhs.denom - rhs_rem).checked_mul(rhs_scale)?)?;
+            let g2 = checked_gcd(t, g)?;
+            let (numer, denom) = (t / g2, rhs_scale.checked_mul(rhs.denom / g2)?);
+
+            // both integer parts plus a fraction in [0, 1), which only overflow if the sum does
+            let (low, high) = (min(lhs_int, rhs_int), max(lhs_int, rhs_int));
+            let (int, numer) = if numer < T::zero() {
+                (low.checked_add(high)?, numer + denom)
+            } else {
+                (low.checked_add(T::one())?.checked_add(high)?, numer)
+            };
+
+            // for a negative integer part go through int + 1, so a final value near MIN still fits
+            let numer = if int < T::zero() && numer > T::zero() {
+                (int + T::one()).checked_mul(denom)?.checked_sub(denom - numer)?
+            } else {
+                int.checked_mul(denom)?.checked_add(numer)?
+            };
+            Some(Rational { numer, denom })
+        }
+
+        pub fn checked_sub(self, rhs: Rational<T>) -> Option<Rational<T>> {
+            self.checked_add(rhs.checked_neg()?)
+        }
+
+        pub fn checked_mul(self, rhs: Rational<T>) -> Option<Rational<T>> {
+            // cross-cancel first so the products stay as small as possible
+            let g1 = checked_gcd(self.numer, rhs.denom)?;
+            let g2 = checked_gcd(rhs.numer, self.denom)?;
+            if g1 == T::zero() || g2 == T::zero() {
+                return Some(Rational::zero());
+            }
+            let numer = self.numer.checked_div(g1)?.checked_mul(rhs.numer.checked_div(g2)?)?;
+            let denom = self.denom.checked_div(g2)?.checked_mul(rhs.denom.checked_div(g1)?)?;
+            Some(Rational { numer, denom })
+        }
+
+        /// `None` on overflow or division by zero
+        pub fn checked_div(self, rhs: Rational<T>) -> Option<Rational<T>> {
+            self.checked_mul(rhs.checked_recip()?)
+        }
+
+        pub fn checked_neg(self) -> Option<Rational<T>> {
+            Some(Rational { numer: self.numer.checked_neg()?, denom: self.denom })
+        }
+    }
+
+    impl<T: Integer + Copy> From<T> for Rational<T> {
+        fn from(val: T) -> Rational<T> {
+            Rational::from_integer(val)
+        }
+    }
+
+    impl<T: Zero + One> Zero for Rational<T> {
+        fn zero() -> Self {
+            Rational { numer: T::zero(), denom: T::one() }
+        }
+    }
+
+    impl<T: One> One for Rational<T> {
+        fn one() -> Self {
+            Rational { numer: T::one(), denom: T::one() }
+        }
+    }
+
+    impl<T: Integer + Copy> Add for Rational<T> {
+        type Output = Self;
+
+        fn add(self, rhs: Self) -> Self {
+            self.checked_add(rhs).expect("Rational overflow")
+        }
+    }
+
+    impl<T: Integer + Copy> Sub for Rational<T> {
+        type Output = Self;
+
+        fn sub(self, rhs: Self) -> Self {
+            self.checked_sub(rhs).expect("Rational overflow")
+        }
+    }
+
+    impl<T: Integer + Copy> Mul for Rational<T> {
+        type Output = Self;
+
+        fn mul(self, rhs: Self) -> Self {
+            self.checked_mul(rhs).expect("Rational overflow")
+        }
+    }
+
+    impl<T: Integer + Copy> Div for Rational<T> {
+        type Output = Self;
+
+        fn div(self, rhs: Self) -> Self {
+            if rhs.numer == T::zero() {
+                panic!("Division by zero");
+            }
+            self.checked_div(rhs).expect("Rational overflow")
+        }
+    }
+
+    impl<T: Integer + Copy> Neg for Rational<T> {
+        type Output = Self;
+
+        fn neg(self) -> Self {
+            self.checked_neg().expect("Rational overflow")
+        }
+    }
+
+    impl<T: Integer + Copy> Ord for Rational<T> {
+        fn cmp(&self, other: &Self) -> Ordering {
+            // compare continued fraction expansions, cross-multiplying could overflow
+            let (mut n1, mut d1) = (self.numer, self.denom);
+            let (mut n2, mut d2) = (other.numer, other.denom);
+            let mut flipped = false;
+            loop {
+                let (q1, r1) = floor_divmod(n1, d1);
+                let (q2, r2) = floor_divmod(n2, d2);
+                let ord = match (q1.cmp(&q2), r1 == T::zero(), r2 == T::zero()) {
+                    (Ordering::Equal, true, true) => Ordering::Equal,
+                    (Ordering::Equal, true, false) => Ordering::Less,
+                    (Ordering::Equal, false, true) => Ordering::Greater,
+                    (Ordering::Equal, false, false) => {
+                        // r1/d1 < r2/d2 iff d1/r1 > d2/r2
+                        (n1, d1, n2, d2) = (d1, r1, d2, r2);
+                        flipped = !flipped;
+                        continue;
+                    }
+                    (ord, _, _) => ord,
+                };
+                return if flipped { ord.reverse() } else { ord };
+            }
+        }
+    }
+
+    impl<T: Integer + Copy> PartialOrd for Rational<T> {
+        fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+            Some(self.cmp(other))
+        }
+    }
+
+    impl<T: Display + One + PartialEq> Display for Rational<T> {
+        fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+            if self.denom == T::one() {
+                write!(f, "{}", self.numer)
+            } else {
+                write!(f, "{}/{}", self.numer, self.denom)
+            }
+        }
+    }
+
+    impl<T: Integer + Copy + FromStr> FromStr for Rational<T> {
+        type Err = ParseRationalError;
+
+        fn from_str(s: &str) -> Result<Self, Self::Err> {
+            let (numer, denom) = match s.trim().split_once('/') {
+                Some((numer, denom)) => (
+                    numer.trim().parse().map_err(|_| ParseRationalError::Numerator)?,
+                    denom.trim().parse().map_err(|_| ParseRationalError::Denominator)?,
+                ),
+                None => (s.trim().parse().map_err(|_| ParseRationalError::Numerator)?, T::one()),
+            };
+            if denom == T::zero() {
+                return Err(ParseRationalError::ZeroDenominator);
+            }
+
+            Rational::checked_new(numer, denom).ok_or(ParseRationalError::Overflow)
+        }
+    }
+
+    impl<T: Integer + Copy> Semiring for Rational<T> {
+        fn checked_add(self, rhs: Self) -> Option<Self> {
+            Rational::checked_add(self, rhs)
+        }
+
+        fn checked_mul(self, rhs: Self) -> Option<Self> {
+            Rational::checked_mul(self, rhs)
+        }
+    }
+
+    impl<T: Integer + Copy> Ring for Rational<T> {
+        fn checked_sub(self, rhs: Self) -> Option<Self> {
+            Rational::checked_sub(self, rhs)
+        }
+
+        fn checked_neg(self) -> Option<Self> {
+            Rational::checked_neg(self)
+        }
+    }
+
+    impl<T: Integer + Copy> Field for Rational<T> {
+        fn is_zero(&self) -> bool {
+            self.numer == T::zero()
+        }
+    }
+
+    /// Solution set of a linear system `Ax = b`
+    pub enum Solution<const N: usize, T> {
+        Unique(Matrix<1, N, T>),
+        /// every `particular + Σ t_i * basis[i]` solves the system
+        Parametric {
+            particular: Matrix<1, N, T>,
+            basis: Vec<Matrix<1, N, T>>,
+        },
+        Inconsistent,
+    }
+
+    /// Brings `rows` into reduced row echelon form, only picking pivots from the first `cols` columns
+    /// so that any augmented columns get carried along. Returns the pivot column of each non-zero row.
+    fn row_reduce<T: Field>(rows: &mut [Vec<T>], cols: usize) -> Vec<usize> {
+        let mut pivots = Vec::new();
+        for col in 0..cols {
+            let r = pivots.len();
+            if r == rows.len() {
+                break;
+            }
+
+            let mut best: Option<usize> = None;
+            for (y, row) in rows.iter().enumerate().skip(r) {
+                if row[col].is_zero() {
+                    continue;
+                }
+                if best.is_none_or(|b| row[col].better_pivot(&rows[b][col])) {
+                    best = Some(y);
+                }
+            }
+            let Some(best) = best else { continue };
+
+            rows.swap(r, best);
+            let inv = T::one() / rows[r][col];
+            rows[r].iter_mut().for_each(|v| *v = *v * inv);
+            rows[r][col] = T::one();
+
+            let pivot_row = rows[r].clone();
+            for (y, row) in rows.iter_mut().enumerate() {
+                let factor = row[col];
+                if y == r || factor.is_zero() {
+                    continue;
+                }
+                for (v, &p) in row.iter_mut().zip(&pivot_row) {
+                    *v = *v - factor * p;
+                }
+                row[col] = T::zero();
+            }
+
+            pivots.push(col);
+        }
+
+        pivots
+    }
+
+    impl<const WIDTH: usize, const HEIGHT: usize, T: Field> Matrix<WIDTH, HEIGHT, T> {
+        fn to_rows(&self) -> Vec<Vec<T>> {
+            self.rows.iter().map(|row| row.to_vec()).collect()
+        }
+
+        /// reduced row echelon form together with the pivot columns
+        pub fn rref(&self) -> (Matrix<WIDTH, HEIGHT, T>, Vec<usize>) {
+            let mut rows = self.to_rows();
+            let pivots = row_reduce(&mut rows, WIDTH);
+
+            let mut result = self.rows;
+            for y in 0..HEIGHT {
+                result[y].copy_from_slice(&rows[y]);
+            }
+
+            (Matrix { rows: result }, pivots)
+        }
+
+        pub fn rank(&self) -> usize {
+            row_reduce(&mut self.to_rows(), WIDTH).len()
+        }
+
+        /// solves `self * x = b` for the column vector `x`
+        pub fn solve(&self, b: &Matrix<1, HEIGHT, T>) -> Solution<WIDTH, T> {
+            let mut rows = self.to_rows();
+            for (row, b) in rows.iter_mut().zip(&b.rows) {
+                row.push(b[0]);
+            }
+            let pivots = row_reduce(&mut rows, WIDTH);
+
+            if rows[pivots.len()..].iter().any(|row| !row[WIDTH].is_zero()) {
+                return Solution::Inconsistent;
+            }
+
+            let mut particular = [[T::zero(); 1]; WIDTH];
+            for (y, &col) in pivots.iter().enumerate() {
+                particular[col][0] = rows[y][WIDTH];
+            }
+            let particular = Matrix { rows: particular };
+
+            if pivots.len() == WIDTH {
+                return Solution::Unique(particular);
+            }
+
+            let basis = (0..WIDTH)
+                .filter(|col| !pivots.contains(col))
+                .map(|free| {
+                    let mut v = [[T::zero(); 1]; WIDTH];
+                    v[free][0] = T::one();
+                    for (y, &col) in pivots.iter().enumerate() {
+                        v[col][0] = T::zero() - rows[y][free];
+                    }
+                    Matrix { rows: v }
+                })
+                .collect();
+
+            Solution::Parametric { particular, basis }
+        }
+    }
+
+    impl<const N: usize, T: Field> Matrix<N, N, T> {
+        pub fn determinant(&self) -> T {
+            let mut rows = self.to_rows();
+            let mut det = T::one();
+            for col in 0..N {
+                let mut best: Option<usize> = None;
+                for (y, row) in rows.iter().enumerate().skip(col) {
+                    if !row[col].is_zero() && best.is_none_or(|b| row[col].better_pivot(&rows[b][col])) {
+                        best = Some(y);
+                    }
+                }
+                let Some(best) = best else { return T::zero() };
+
+                if best != col {
+                    rows.swap(col, best);
+                    det = T::zero() - det;
+                }
+                let pivot = rows[col][col];
+                det = det * pivot;
+
+                let pivot_row = rows[col].clone();
+                for row in rows[col + 1..].iter_mut() {
+                    let factor = row[col] / pivot;
+                    for (v, &p) in row.iter_mut().zip(&pivot_row).skip(col) {
+                        *v = *v - factor * p;
+                    }
+                }
+            }
+
+            det
+        }
+
+        /// `None` if the matrix is singular
+        pub fn inverse(&self) -> Option<Matrix<N, N, T>> {
+            let mut rows = self.to_rows();
+            for (y, row) in rows.iter_mut().enumerate() {
+                row.extend((0..N).map(|x| if x == y { T::one() } else { T::zero() }));
+            }
+            if row_reduce(&mut rows, N).len() < N {
+                return None;
+            }
+
+            let mut result = self.rows;
+            for y in 0..N {
+                result[y].copy_from_slice(&rows[y][N..]);
+            }
+
+            Some(Matrix { rows: result })
+        }
+    }
+
+    impl<
+        const N: usize,
+        T: Ring + Copy + Div<Output = T> + PartialEq,
+    > Matrix<N, N, T>
+    {
+        /// fraction-free determinant (Bareiss), every division is exact so this works on integers
+        pub fn determinant_bareiss(&self) -> T {
+            if N == 0 {
+                return T::one();
+            }
+
+            let mut m = self.rows;
+            let mut negate = false;
+            let mut prev = T::one();
+            for k in 0..N - 1 {
+                if m[k][k] == T::zero() {
+                    let Some(swap) = (k + 1..N).find(|&y| m[y][k] != T::zero()) else {
+                        return T::zero();
+                    };
+                    m.swap(k, swap);
+                    negate = !negate;
+                }
+
+                for y in k + 1..N {
+                    for x in k + 1..N {
+                        m[y][x] = (m[y][x] * m[k][k] - m[y][k] * m[k][x]) / prev;
+                    }
+                }
+                prev = m[k][k];
+            }
+
+            if negate { T::zero() - m[N - 1][N - 1] } else { m[N - 1][N - 1] }
+        }
+    }
+
+    /// Vector over GF(2), packed into 64-bit words. Bits past `len` are always zero.
+    #[derive(Clone, PartialEq, Eq, Hash, Debug)]
+    pub struct BitVec {
+        words: Vec<u64>,
+        len: usize,
+    }
+
+    impl BitVec {
+        pub fn zeros(len: usize) -> BitVec {
+            BitVec { words: vec![0; len.div_ceil(64)], len }
+        }
+
+        pub fn from_bools(bits: &[bool]) -> BitVec {
+            let mut result = BitVec::zeros(bits.len());
+            for (i, &bit) in bits.iter().enumerate() {
+                result.set(i, bit);
+            }
+            result
+        }
+
+        pub fn len(&self) -> usize {
+            self.len
+        }
+
+        pub fn is_empty(&self) -> bool {
+            self.len == 0
+        }
+
+        pub fn get(&self, i: usize) -> bool {
+            assert!(i < self.len, "Bit index out of range");
+            self.words[i / 64] >> (i % 64) & 1 == 1
+        }
+
+        pub fn set(&mut self, i: usize, val: bool) {
+            assert!(i < self.len, "Bit index out of range");
+            if val {
+                self.words[i / 64] |= 1 << (i % 64);
+            } else {
+                self.words[i / 64] &= !(1 << (i % 64));
+            }
+        }
+
+        pub fn flip(&mut self, i: usize) {
+            assert!(i < self.len, "Bit index out of range");
+            self.words[i / 64] ^= 1 << (i % 64);
+        }
+
+        pub fn count_ones(&self) -> usize {
+            self.words.iter().map(|w| w.count_ones() as usize).sum()
+        }
+
+        pub fn is_zero(&self) -> bool {
+            self.words.iter().all(|&w| w == 0)
+        }
+
+        pub fn xor_assign(&mut self, other: &BitVec) {
+            assert_eq!(self.len, other.len, "Bit vector lengths differ");
+            for (w, o) in self.words.iter_mut().zip(&other.words) {
+                *w ^= o;
+            }
+        }
+
+        /// indices of all set bits, in ascending order
+        pub fn iter_ones(&self) -> impl Iterator<Item = usize> + '_ {
+            (0..self.len).filter(|&i| self.get(i))
+        }
+
+        pub fn to_bools(&self) -> Vec<bool> {
+            (0..self.len).map(|i| self.get(i)).collect()
+        }
+
+        fn resized(&self, len: usize) -> BitVec {
+            let mut result = BitVec::zeros(len);
+            for i in self.iter_ones().take_while(|&i| i < len) {
+                result.set(i, true);
+            }
+            result
+        }
+    }
+
+    /// Matrix over GF(2) with bit-packed rows, for toggle puzzles where `get(x, y)` says
+    /// whether switch `x` affects light `y`.
+    #[derive(Clone, PartialEq, Eq, Hash, Debug)]
+    pub struct BitMatrix {
+        rows: Vec<BitVec>,
+        width: usize,
+    }
+
+    /// Reduces `rows` to reduced row echelon form over GF(2), only pivoting on the first `cols` columns.
+    /// Returns the pivot column of each non-zero row.
+    fn bit_row_reduce(rows: &mut [BitVec], cols: usize) -> Vec<usize> {
+        let mut pivots = Vec::new();
+        for col in 0..cols {
+            let r = pivots.len();
+            if r == rows.len() {
+                break;
+            }
+            let Some(found) = (r..rows.len()).find(|&y| rows[y].get(col)) else { continue };
+
+            rows.swap(r, found);
+            let pivot_row = rows[r].clone();
+            for (y, row) in rows.iter_mut().enumerate() {
+                if y != r && row.get(col) {
+                    row.xor_assign(&pivot_row);
+                }
+            }
+            pivots.push(col);
+        }
+
+        pivots
+    }
+
+    impl BitMatrix {
+        pub fn new(width: usize, height: usize) -> BitMatrix {
+            BitMatrix { rows: vec![BitVec::zeros(width); height], width }
+        }
+
+        pub fn from_rows(rows: Vec<BitVec>) -> BitMatrix {
+            let width = rows.first().map_or(0, BitVec::len);
+            if rows.iter().any(|row| row.len() != width) {
+                panic!("Bad input length");
+            }
+            BitMatrix { rows, width }
+        }
+
+        pub fn from_bools(data: &[Vec<bool>]) -> BitMatrix {
+            BitMatrix::from_rows(data.iter().map(|row| BitVec::from_bools(row)).collect())
+        }
+
+        pub fn width(&self) -> usize {
+            self.width
+        }
+
+        pub fn height(&self) -> usize {
+            self.rows.len()
+        }
+
+        pub fn get(&self, x: usize, y: usize) -> bool {
+            self.rows[y].get(x)
+        }
+
+        pub fn set(&mut self, x: usize, y: usize, val: bool) {
+            self.rows[y].set(x, val)
+        }
+
+        pub fn flip(&mut self, x: usize, y: usize) {
+            self.rows[y].flip(x)
+        }
+
+        pub fn row(&self, y: usize) -> &BitVec {
+            &self.rows[y]
+        }
+
+        /// row `dst` ^= row `src`
+        pub fn xor_rows(&mut self, dst: usize, src: usize) {
+            assert_ne!(dst, src, "Cannot xor a row with itself");
+            let src = self.rows[src].clone();
+            self.rows[dst].xor_assign(&src);
+        }
+
+        pub fn swap_rows(&mut self, a: usize, b: usize) {
+            self.rows.swap(a, b);
+        }
+
+        /// reduces the matrix in place, returns the pivot columns
+        pub fn rref(&mut self) -> Vec<usize> {
+            bit_row_reduce(&mut self.rows, self.width)
+        }
+
+        pub fn rank(&self) -> usize {
+            self.clone().rref().len()
+        }
+
+        /// `self * v`, i.e. which lights end up toggled when pressing the switches in `v`
+        pub fn mul_vec(&self, v: &BitVec) -> BitVec {
+            assert_eq!(v.len(), self.width, "Bit vector lengths differ");
+            let mut result = BitVec::zeros(self.height());
+            for (y, row) in self.rows.iter().enumerate() {
+                let parity = row.words.iter().zip(&v.words).map(|(a, b)| (a & b).count_ones()).sum::<u32>();
+                result.set(y, parity % 2 == 1);
+            }
+            result
+        }
+
+        /// basis of all `x` with `self * x = 0`
+        pub fn null_space(&self) -> Vec<BitVec> {
+            let mut rows = self.rows.clone();
+            let pivots = bit_row_reduce(&mut rows, self.width);
+            Self::free_basis(&rows, &pivots, self.width)
+        }
+
+        fn free_basis(rows: &[BitVec], pivots: &[usize], width: usize) -> Vec<BitVec> {
+            (0..width)
+                .filter(|col| !pivots.contains(col))
+                .map(|free| {
+                    let mut v = BitVec::zeros(width);
+                    v.set(free, true);
+                    for (y, &col) in pivots.iter().enumerate() {
+                        if rows[y].get(free) {
+                            v.set(col, true);
+                        }
+                    }
+                    v
+                })
+                .collect()
+        }
+
+        /// one solution of `self * x = b` together with the null space basis,
+        /// or `None` if the system is inconsistent
+        pub fn solve(&self, b: &BitVec) -> Option<(BitVec, Vec<BitVec>)> {
+            assert_eq!(b.len(), self.height(), "Bit vector lengths differ");
+            let mut rows: Vec<BitVec> = self.rows.iter()
+                .enumerate()
+                .map(|(y, row)| {
+                    let mut row = row.resized(self.width + 1);
+                    row.set(self.width, b.get(y));
+                    row
+                })
+                .collect();
+            let pivots = bit_row_reduce(&mut rows, self.width);
+
+            if rows[pivots.len()..].iter().any(|row| row.get(self.width)) {
+                return None;
+            }
+
+            let mut particular = BitVec::zeros(self.width);
+            for (y, &col) in pivots.iter().enumerate() {
+                particular.set(col, rows[y].get(self.width));
+            }
+
+            Some((particular, Self::free_basis(&rows, &pivots, self.width)))
+        }
+
+        /// solution of `self * x = b` with the fewest set bits ("fewest button presses").
+        /// Walks every combination of null space vectors, so it's exponential in the nullity.
+        pub fn min_weight_solution(&self, b: &BitVec) -> Option<BitVec> {
+            let (mut current, basis) = self.solve(b)?;
+            assert!(basis.len() < 64, "Null space too large to enumerate");
+
+            let mut best = current.clone();
+            // gray code order, each step differs from the previous by a single basis vector
+            for step in 1u64..(1 << basis.len()) {
+                current.xor_assign(&basis[step.trailing_zeros() as usize]);
+                if current.count_ones() < best.count_ones() {
+                    best = current.clone();
+                }
+            }
+
+            Some(best)
+        }
+    }
+
+    #[derive(Clone, Copy, PartialEq, Eq, Debug)]
+    pub enum Relation {
+        Le,
+        Eq,
+        Ge,
+    }
+
+    #[derive(Clone, Copy, PartialEq, Eq, Debug)]
+    pub enum LpError {
+        Infeasible,
+        Unbounded,
+        /// branch-and-bound used up its node budget before settling the integer optimum
+        NodeLimit,
+    }
+
+    /// Minimise `objective · x` subject to linear constraints and `x ≥ 0`
+    #[derive(Clone, Debug)]
+    pub struct LinearProgram<T> {
+        objective: Vec<T>,
+        constraints: Vec<(Vec<T>, Relation, T)>,
+    }
+
+    /// Simplex tableau in canonical form: row `i` has a unit entry in column `basis[i]`,
+    /// the last entry of every row is its right hand side.
+    struct Tableau<T> {
+        rows: Vec<Vec<T>>,
+        basis: Vec<usize>,
+    }
+
+    impl<T: Field + PartialOrd> Tableau<T> {
+        fn rhs(&self, row: usize) -> T {
+            *self.rows[row].last().unwrap()
+        }
+
+        fn pivot(&mut self, row: usize, col: usize) {
+            let inv = T::one() / self.rows[row][col];
+            self.rows[row].iter_mut().for_each(|v| *v = *v * inv);
+            self.rows[row][col] = T::one();
+
+            let pivot_row = self.rows[row].clone();
+            for (y, other) in self.rows.iter_mut().enumerate() {
+                let factor = other[col];
+                if y == row || factor.is_zero() {
+                    continue;
+                }
+                for (v, &p) in other.iter_mut().zip(&pivot_row) {
+                    *v = *v - factor * p;
+                }
+                other[col] = T::zero();
+            }
+            self.basis[row] = col;
+        }
+
+        fn value(&self, cost: &[T]) -> T {
+            (0..self.rows.len()).fold(T::zero(), |acc, y| acc + cost[self.basis[y]] * self.rhs(y))
+        }
+
+        /// minimise `cost · x`, only letting the first `cols` columns enter the basis.
+        /// Uses Bland's rule so degenerate pivots cannot cycle.
+        fn optimise(&mut self, cost: &[T], cols: usize) -> Result<(), LpError> {
+            loop {
+                let entering = (0..cols).find(|&col| {
+                    let reduced = (0..self.rows.len())
+                        .fold(cost[col], |acc, y| acc - cost[self.basis[y]] * self.rows[y][col]);
+                    reduced < T::zero() && !reduced.is_zero()
+                });
+                let Some(col) = entering else { return Ok(()) };
+
+                let mut leaving: Option<(usize, T)> = None;
+                for y in 0..self.rows.len() {
+                    let a = self.rows[y][col];
+                    if a.is_zero() || a < T::zero() {
+                        continue;
+                    }
+                    let ratio = self.rhs(y) / a;
+                    let better = match leaving {
+                        None => true,
+                        Some((best, best_ratio)) => {
+                            ratio < best_ratio
+                                || ((ratio - best_ratio).is_zero() && self.basis[y] < self.basis[best])
+                        }
+                    };
+                    if better {
+                        leaving = Some((y, ratio));
+                    }
+                }
+                let Some((row, _)) = leaving else { return Err(LpError::Unbounded) };
+
+                self.pivot(row, col);
+            }
+        }
+    }
+
+    impl<T: Field + PartialOrd> LinearProgram<T> {
+        pub fn new(objective: Vec<T>) -> LinearProgram<T> {
+            LinearProgram { objective, constraints: Vec::new() }
+        }
+
+        /// adds `coeffs · x (<=|=|>=) rhs`
+        pub fn add_constraint(&mut self, coeffs: Vec<T>, relation: Relation, rhs: T) {
+            if coeffs.len() != self.objective.len() {
+                panic!("Bad constraint length");
+            }
+            self.constraints.push((coeffs, relation, rhs));
+        }
+
+        pub fn num_vars(&self) -> usize {
+            self.objective.len()
+        }
+
+        /// bounds `x[var]` by `bound`, replacing an earlier bound in the same direction so deep
+        /// branch-and-bound paths don't keep growing the tableau
+        fn tighten(&mut self, var: usize, relation: Relation, bound: T) {
+            let mut unit = vec![T::zero(); self.num_vars()];
+            unit[var] = T::one();
+            match self.constraints.iter_mut().find(|(coeffs, rel, _)| *rel == relation && *coeffs == unit) {
+                Some(existing) => existing.2 = bound,
+                None => self.constraints.push((unit, relation, bound)),
+            }
+        }
+
+        /// optimal value and a point attaining it, solved with the two-phase simplex method
+        pub fn minimise(&self) -> Result<(T, Vec<T>), LpError> {
+            let n = self.num_vars();
+            let m = self.constraints.len();
+
+            // flip rows so every right hand side is non-negative
+            let constraints: Vec<(Vec<T>, Relation, T)> = self.constraints.iter()
+                .map(|(coeffs, relation, rhs)| {
+                    if *rhs < T::zero() {
+                        let flipped = match relation {
+                            Relation::Le => Relation::Ge,
+                            Relation::Eq => Relation::Eq,
+                            Relation::Ge => Relation::Le,
+                        };
+                        (coeffs.iter().map(|&c| T::zero() - c).collect(), flipped, T::zero() - *rhs)
+                    } else {
+                        (coeffs.clone(), *relation, *rhs)
+                    }
+                })
+                .collect();
+
+            // columns: original variables, one slack/surplus per inequality, then artificials
+            let num_slack = constraints.iter().filter(|c| c.1 != Relation::Eq).count();
+            let num_artificial = constraints.iter().filter(|c| c.1 != Relation::Le).count();
+            let real_cols = n + num_slack;
+            let total_cols = real_cols + num_artificial;
+
+            let mut tableau = Tableau { rows: Vec::with_capacity(m), basis: Vec::with_capacity(m) };
+            let mut slack = n;
+            let mut artificial = real_cols;
+            for (coeffs, relation, rhs) in constraints {
+                let mut row = coeffs;
+                row.resize(total_cols + 1, T::zero());
+                row[total_cols] = rhs;
+                match relation {
+                    Relation::Le => {
+                        row[slack] = T::one();
+                        tableau.basis.push(slack);
+                        slack += 1;
+                    }
+                    Relation::Ge => {
+                        row[slack] = T::zero() - T::one();
+                        row[artificial] = T::one();
+                        tableau.basis.push(artificial);
+                        slack += 1;
+                        artificial += 1;
+                    }
+                    Relation::Eq => {
+                        row[artificial] = T::one();
+                        tableau.basis.push(artificial);
+                        artificial += 1;
+                    }
+                }
+                tableau.rows.push(row);
+            }
+
+            // phase 1: drive the artificial variables to zero
+            let mut phase_one = vec![T::zero(); total_cols];
+            phase_one[real_cols..].iter_mut().for_each(|c| *c = T::one());
+            tableau.optimise(&phase_one, total_cols)?;
+            if !tableau.value(&phase_one).is_zero() {
+                return Err(LpError::Infeasible);
+            }
+
+            // pivot remaining (zero valued) artificials out, dropping rows that turn out redundant
+            let mut y = 0;
+            while y < tableau.rows.len() {
+                if tableau.basis[y] < real_cols {
+                    y += 1;
+                    continue;
+                }
+                match (0..real_cols).find(|&col| !tableau.rows[y][col].is_zero()) {
+                    Some(col) => {
+                        tableau.pivot(y, col);
+                        y += 1;
+                    }
+                    None => {
+                        tableau.rows.remove(y);
+                        tableau.basis.remove(y);
+                    }
+                }
+            }
+
+            // phase 2: the actual objective, artificials may no longer enter
+            let mut cost = self.objective.clone();
+            cost.resize(total_cols, T::zero());
+            tableau.optimise(&cost, real_cols)?;
+
+            let mut x = vec![T::zero(); n];
+            for (y, &col) in tableau.basis.iter().enumerate() {
+                if col < n {
+                    x[col] = tableau.rhs(y);
+                }
+            }
+
+            Ok((tableau.value(&cost), x))
+        }
+    }
+
+    impl<T: Integer + Copy> LinearProgram<Rational<T>> {
+        /// optimum over non-negative integer points, found by branch-and-bound on the LP relaxation.
+        /// An unbounded relaxation is reported as `Unbounded` even if few integer points exist.
+        /// Gives up with `NodeLimit` after 10000 relaxations, see `minimise_integer_within`.
+        pub fn minimise_integer(&self) -> Result<(Rational<T>, Vec<T>), LpError> {
+            self.minimise_integer_within(10_000)
+        }
+
+        /// `minimise_integer` solving at most `max_nodes` relaxations. Without a bound on the
+        /// variables an unbounded region with no integer point, like `2x - 2y = 1`, would
+        /// otherwise be branched on forever.
+        pub fn minimise_integer_within(&self, max_nodes: usize) -> Result<(Rational<T>, Vec<T>), LpError> {
+            let mut best: Option<(Rational<T>, Vec<T>)> = None;
+            let mut stack = vec![self.clone()];
+            let mut nodes = 0;
+
+            while let Some(program) = stack.pop() {
+                if nodes == max_nodes {
+                    return Err(LpError::NodeLimit);
+                }
+                nodes += 1;
+                let (value, x) = match program.minimise() {
+                    Ok(solution) => solution,
+                    Err(LpError::Infeasible) => continue,
+                    Err(err) => return Err(err),
+                };
+                if best.as_ref().is_some_and(|(best_value, _)| value >= *best_value) {
+                    continue;
+                }
+
+                match x.iter().position(|v| !v.is_integer()) {
+                    None => best = Some((value, x.iter().map(|v| v.numer()).collect())),
+                    Some(k) => {
+                        let mut up = program.clone();
+                        up.tighten(k, Relation::Ge, Rational::from_integer(x[k].ceil()));
+                        stack.push(up);
+
+                        let mut down = program;
+                        down.tighten(k, Relation::Le, Rational::from_integer(x[k].floor()));
+                        stack.push(down);
+                    }
+                }
+            }
+
+            best.ok_or(LpError::Infeasible)
+        }
+    }
+
+    /// Arbitrary-precision unsigned integer, little-endian base 2^32 limbs without trailing zeros.
+    #[derive(Clone, PartialEq, Eq, Hash, Debug, Default)]
+    pub struct BigUint {
+        limbs: Vec<u32>,
+    }
+
+    /// Arbitrary-precision signed integer. Zero is never negative.
+    #[derive(Clone, PartialEq, Eq, Hash, Debug, Default)]
+    pub struct BigInt {
+        negative: bool,
+        magnitude: BigUint,
+    }
+
+    #[derive(Clone, Copy, PartialEq, Eq, Debug)]
+    pub struct ParseBigIntError;
+
+    /// implements the owned/borrowed operator variants in terms of `&a op &b`
+    macro_rules! forward_big_binop {
+        ($ty:ty, $tr:ident, $method:ident) => {
+            impl $tr<$ty> for $ty {
+                type Output = $ty;
+
+                fn $method(self, rhs: $ty) -> $ty {
+                    (&self).$method(&rhs)
+                }
+            }
+
+            impl $tr<&$ty> for $ty {
+                type Output = $ty;
+
+                fn $method(self, rhs: &$ty) -> $ty {
+                    (&self).$method(rhs)
+                }
+            }
+
+            impl $tr<$ty> for &$ty {
+                type Output = $ty;
+
+                fn $method(self, rhs: $ty) -> $ty {
+                    self.$method(&rhs)
+                }
+            }
+        };
+    }
+
+    impl BigUint {
+        fn from_limbs(mut limbs: Vec<u32>) -> BigUint {
+            while limbs.last() == Some(&0) {
+                limbs.pop();
+            }
+            BigUint { limbs }
+        }
+
+        pub fn is_zero(&self) -> bool {
+            self.limbs.is_empty()
+        }
+
+        pub fn to_u64(&self) -> Option<u64> {
+            match self.limbs[..] {
+                [] => Some(0),
+                [lo] => Some(lo as u64),
+                [lo, hi] => Some((hi as u64) << 32 | lo as u64),
+                _ => None,
+            }
+        }
+
+        pub fn checked_sub(&self, rhs: &BigUint) -> Option<BigUint> {
+            if self < rhs {
+                return None;
+            }
+
+            let mut limbs = Vec::with_capacity(self.limbs.len());
+            let mut borrow = 0i64;
+            for (i, &a) in self.limbs.iter().enumerate() {
+                let t = a as i64 - rhs.limbs.get(i).copied().unwrap_or(0) as i64 - borrow;
+                limbs.push(t as u32);
+                borrow = if t < 0 { 1 } else { 0 };
+            }
+
+            Some(BigUint::from_limbs(limbs))
+        }
+
+        /// quotient and remainder, panics on division by zero
+        pub fn div_rem(&self, rhs: &BigUint) -> (BigUint, BigUint) {
+            if rhs.is_zero() {
+                panic!("Division by zero");
+            }
+            if self < rhs {
+                return (BigUint::zero(), self.clone());
+            }
+            if rhs.limbs.len() == 1 {
+                let (q, r) = self.div_rem_small(rhs.limbs[0]);
+                return (q, BigUint::from(r as u64));
+            }
+
+            // Knuth's algorithm D, normalise so the top limb of the divisor has its high bit set
+            let shift = rhs.limbs.last().unwrap().leading_zeros();
+            let v = shl_limbs(&rhs.limbs, shift);
+            let mut u = shl_limbs(&self.limbs, shift);
+            u.resize(self.limbs.len() + 1, 0);
+            let n = v.len();
+            let m = self.limbs.len() - n;
+            let base = 1u64 << 32;
+
+            let mut q = vec![0u32; m + 1];
+            for j in (0..=m).rev() {
+                let num = (u[j + n] as u64) << 32 | u[j + n - 1] as u64;
+                let mut qhat = num / v[n - 1] as u64;
+                let mut rhat = num % v[n - 1] as u64;
+                while qhat >= base || qhat * v[n - 2] as u64 > (rhat << 32 | u[j + n - 2] as u64) {
+                    qhat -= 1;
+                    rhat += v[n - 1] as u64;
+                    if rhat >= base {
+                        break;
+                    }
+                }
+
+                let mut borrow = 0i64;
+                for i in 0..n {
+                    let p = qhat * v[i] as u64;
+                    let t = u[i + j] as i64 - borrow - (p & 0xFFFF_FFFF) as i64;
+                    u[i + j] = t as u32;
+                    borrow = (p >> 32) as i64 - (t >> 32);
+                }
+                let t = u[j + n] as i64 - borrow;
+                u[j + n] = t as u32;
+
+                if t < 0 {
+                    // qhat was one too large, add the divisor back
+                    qhat -= 1;
+                    let mut carry = 0u64;
+                    for i in 0..n {
+                        let t = u[i + j] as u64 + v[i] as u64 + carry;
+                        u[i + j] = t as u32;
+                        carry = t >> 32;
+                    }
+                    u[j + n] = u[j + n].wrapping_add(carry as u32);
+                }
+                q[j] = qhat as u32;
+            }
+
+            u.truncate(n);
+            (BigUint::from_limbs(q), BigUint::from_limbs(shr_limbs(&u, shift)))
+        }
+
+        fn div_rem_small(&self, rhs: u32) -> (BigUint, u32) {
+            let mut q = vec![0u32; self.limbs.len()];
+            let mut rem = 0u64;
+            for (i, &limb) in self.limbs.iter().enumerate().rev() {
+                let cur = rem << 32 | limb as u64;
+                q[i] = (cur / rhs as u64) as u32;
+                rem = cur % rhs as u64;
+            }
+            (BigUint::from_limbs(q), rem as u32)
+        }
+
+        pub fn pow(&self, mut exp: u32) -> BigUint {
+            let mut base = self.clone();
+            let mut result = BigUint::one();
+            while exp > 0 {
+                if exp & 1 == 1 {
+                    result = &result * &base;
+                }
+                exp >>= 1;
+                if exp > 0 {
+                    base = &base * &base;
+                }
+            }
+            result
+        }
+
+        pub fn gcd(&self, other: &BigUint) -> BigUint {
+            let mut a = self.clone();
+            let mut b = other.clone();
+            while !b.is_zero() {
+                let r = &a % &b;
+                a = b;
+                b = r;
+            }
+            a
+        }
+    }
+
+    fn shl_limbs(limbs: &[u32], shift: u32) -> Vec<u32> {
+        let mut result = Vec::with_capacity(limbs.len() + 1);
+        let mut carry = 0u32;
+        for &limb in limbs {
+            let wide = (limb as u64) << shift;
+            result.push(wide as u32 | carry);
+            carry = (wide >> 32) as u32;
+        }
+        if carry != 0 {
+            result.push(carry);
+        }
+        result
+    }
+
+    fn shr_limbs(limbs: &[u32], shift: u32) -> Vec<u32> {
+        let mut result = vec![0u32; limbs.len()];
+        for i in 0..limbs.len() {
+            let wide = (limbs.get(i + 1).copied().unwrap_or(0) as u64) << 32 | limbs[i] as u64;
+            result[i] = (wide >> shift) as u32;
+        }
+        result
+    }
+
+    impl Zero for BigUint {
+        fn zero() -> Self {
+            BigUint { limbs: Vec::new() }
+        }
+    }
+
+    impl One for BigUint {
+        fn one() -> Self {
+            BigUint { limbs: vec![1] }
+        }
+    }
+
+    impl From<u64> for BigUint {
+        fn from(val: u64) -> BigUint {
+            BigUint::from_limbs(vec![val as u32, (val >> 32) as u32])
+        }
+    }
+
+    impl Ord for BigUint {
+        fn cmp(&self, other: &Self) -> Ordering {
+            self.limbs.len().cmp(&other.limbs.len())
+                .then_with(|| self.limbs.iter().rev().cmp(other.limbs.iter().rev()))
+        }
+    }
+
+    impl PartialOrd for BigUint {
+        fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+            Some(self.cmp(other))
+        }
+    }
+
+    impl Add<&BigUint> for &BigUint {
+        type Output = BigUint;
+
+        fn add(self, rhs: &BigUint) -> BigUint {
+            let len = max(self.limbs.len(), rhs.limbs.len());
+            let mut limbs = Vec::with_capacity(len + 1);
+            let mut carry = 0u64;
+            for i in 0..len {
+                let t = self.limbs.get(i).copied().unwrap_or(0) as u64
+                    + rhs.limbs.get(i).copied().unwrap_or(0) as u64
+                    + carry;
+                limbs.push(t as u32);
+                carry = t >> 32;
+            }
+            limbs.push(carry as u32);
+            BigUint::from_limbs(limbs)
+        }
+    }
+
+    impl Sub<&BigUint> for &BigUint {
+        type Output = BigUint;
+
+        fn sub(self, rhs: &BigUint) -> BigUint {
+            self.checked_sub(rhs).expect("BigUint subtraction underflow")
+        }
+    }
+
+    impl Mul<&BigUint> for &BigUint {
+        type Output = BigUint;
+
+        fn mul(self, rhs: &BigUint) -> BigUint {
+            if self.is_zero() || rhs.is_zero() {
+                return BigUint::zero();
+            }
+
+            let mut limbs = vec![0u32; self.limbs.len() + rhs.limbs.len()];
+            for (i, &a) in self.limbs.iter().enumerate() {
+                let mut carry = 0u64;
+                for (j, &b) in rhs.limbs.iter().enumerate() {
+                    let t = a as u64 * b as u64 + limbs[i + j] as u64 + carry;
+                    limbs[i + j] = t as u32;
+                    carry = t >> 32;
+                }
+                limbs[i + rhs.limbs.len()] = carry as u32;
+            }
+            BigUint::from_limbs(limbs)
+        }
+    }
+
+    impl Div<&BigUint> for &BigUint {
+        type Output = BigUint;
+
+        fn div(self, rhs: &BigUint) -> BigUint {
+            self.div_rem(rhs).0
+        }
+    }
+
+    impl Rem<&BigUint> for &BigUint {
+        type Output = BigUint;
+
+        fn rem(self, rhs: &BigUint) -> BigUint {
+            self.div_rem(rhs).1
+        }
+    }
+
+    forward_big_binop!(BigUint, Add, add);
+    forward_big_binop!(BigUint, Sub, sub);
+    forward_big_binop!(BigUint, Mul, mul);
+    forward_big_binop!(BigUint, Div, div);
+    forward_big_binop!(BigUint, Rem, rem);
+
+    /// only a `Semiring`, there are no negatives to make subtraction total
+    impl Semiring for BigUint {}
+
+    impl Display for BigUint {
+        fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+            if self.is_zero() {
+                return f.pad("0");
+            }
+
+            let mut chunks = Vec::new();
+            let mut rest = self.clone();
+            while !rest.is_zero() {
+                let (q, r) = rest.div_rem_small(1_000_000_000);
+                chunks.push(r);
+                rest = q;
+            }
+
+            let mut digits = chunks.pop().unwrap().to_string();
+            for chunk in chunks.iter().rev() {
+                digits.push_str(&format!("{:09}", chunk));
+            }
+            f.pad(&digits)
+        }
+    }
+
+    impl FromStr for BigUint {
+        type Err = ParseBigIntError;
+
+        fn from_str(s: &str) -> Result<Self, Self::Err> {
+            if s.is_empty() || !s.bytes().all(|b| b.is_ascii_digit()) {
+                return Err(ParseBigIntError);
+            }
+
+            let mut result = BigUint::zero();
+            for chunk in s.as_bytes().chunks(9) {
+                let digits = str::from_utf8(chunk).unwrap();
+                let scale = BigUint::from(10u64.pow(digits.len() as u32));
+                result = result * scale + BigUint::from(digits.parse::<u64>().unwrap());
+            }
+            Ok(result)
+        }
+    }
+
+    impl BigInt {
+        fn from_parts(negative: bool, magnitude: BigUint) -> BigInt {
+            BigInt { negative: negative && !magnitude.is_zero(), magnitude }
+        }
+
+        pub fn is_zero(&self) -> bool {
+            self.magnitude.is_zero()
+        }
+
+        pub fn is_negative(&self) -> bool {
+            self.negative
+        }
+
+        pub fn magnitude(&self) -> &BigUint {
+            &self.magnitude
+        }
+
+        pub fn abs(&self) -> BigInt {
+            BigInt::from_parts(false, self.magnitude.clone())
+        }
+
+        /// quotient rounded towards zero and remainder with the sign of `self`, like the primitive types
+        pub fn div_rem(&self, rhs: &BigInt) -> (BigInt, BigInt) {
+            let (q, r) = self.magnitude.div_rem(&rhs.magnitude);
+            (BigInt::from_parts(self.negative != rhs.negative, q), BigInt::from_parts(self.negative, r))
+        }
+
+        pub fn pow(&self, exp: u32) -> BigInt {
+            BigInt::from_parts(self.negative && exp % 2 == 1, self.magnitude.pow(exp))
+        }
+
+        /// non-negative greatest common divisor
+        pub fn gcd(&self, other: &BigInt) -> BigInt {
+            BigInt::from_parts(false, self.magnitude.gcd(&other.magnitude))
+        }
+    }
+
+    impl Zero for BigInt {
+        fn zero() -> Self {
+            BigInt::from_parts(false, BigUint::zero())
+        }
+    }
+
+    impl One for BigInt {
+        fn one() -> Self {
+            BigInt::from_parts(false, BigUint::one())
+        }
+    }
+
+    impl From<BigUint> for BigInt {
+        fn from(val: BigUint) -> BigInt {
+            BigInt::from_parts(false, val)
+        }
+    }
+
+    impl From<u64> for BigInt {
+        fn from(val: u64) -> BigInt {
+            BigInt::from(BigUint::from(val))
+        }
+    }
+
+    impl From<i64> for BigInt {
+        fn from(val: i64) -> BigInt {
+            BigInt::from_parts(val < 0, BigUint::from(val.unsigned_abs()))
+        }
+    }
+
+    impl Ord for BigInt {
+        fn cmp(&self, other: &Self) -> Ordering {
+            match (self.negative, other.negative) {
+                (false, false) => self.magnitude.cmp(&other.magnitude),
+                (true, true) => other.magnitude.cmp(&self.magnitude),
+                (false, true) => Ordering::Greater,
+                (true, false) => Ordering::Less,
+            }
+        }
+    }
+
+    impl PartialOrd for BigInt {
+        fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+            Some(self.cmp(other))
+        }
+    }
+
+    impl Neg for &BigInt {
+        type Output = BigInt;
+
+        fn neg(self) -> BigInt {
+            BigInt::from_parts(!self.negative, self.magnitude.clone())
+        }
+    }
+
+    impl Neg for BigInt {
+        type Output = BigInt;
+
+        fn neg(self) -> BigInt {
+            BigInt::from_parts(!self.negative, self.magnitude)
+        }
+    }
+
+    impl Add<&BigInt> for &BigInt {
+        type Output = BigInt;
+
+        fn add(self, rhs: &BigInt) -> BigInt {
+            if self.negative == rhs.negative {
+                return BigInt::from_parts(self.negative, &self.magnitude + &rhs.magnitude);
+            }
+            if self.magnitude >= rhs.magnitude {
+                BigInt::from_parts(self.negative, &self.magnitude - &rhs.magnitude)
+            } else {
+                BigInt::from_parts(rhs.negative, &rhs.magnitude - &self.magnitude)
+            }
+        }
+    }
+
+    impl Sub<&BigInt> for &BigInt {
+        type Output = BigInt;
+
+        fn sub(self, rhs: &BigInt) -> BigInt {
+            self + &-rhs
+        }
+    }
+
+    impl Mul<&BigInt> for &BigInt {
+        type Output = BigInt;
+
+        fn mul(self, rhs: &BigInt) -> BigInt {
+            BigInt::from_parts(self.negative != rhs.negative, &self.magnitude * &rhs.magnitude)
+        }
+    }
+
+    impl Div<&BigInt> for &BigInt {
+        type Output = BigInt;
+
+        fn div(self, rhs: &BigInt) -> BigInt {
+            self.div_rem(rhs).0
+        }
+    }
+
+    impl Rem<&BigInt> for &BigInt {
+        type Output = BigInt;
+
+        fn rem(self, rhs: &BigInt) -> BigInt {
+            self.div_rem(rhs).1
+        }
+    }
+
+    forward_big_binop!(BigInt, Add, add);
+    forward_big_binop!(BigInt, Sub, sub);
+    forward_big_binop!(BigInt, Mul, mul);
+    forward_big_binop!(BigInt, Div, div);
+    forward_big_binop!(BigInt, Rem, rem);
+
+    impl Semiring for BigInt {}
+
+    impl Ring for BigInt {}
+
+    /// never overflows, so every checked operation succeeds and wrapping or saturating is exact
+    impl Integer for BigInt {
+        fn checked_div(self, rhs: Self) -> Option<Self> {
+            if rhs.is_zero() { None } else { Some(self / rhs) }
+        }
+
+        fn checked_rem(self, rhs: Self) -> Option<Self> {
+            if rhs.is_zero() { None } else { Some(self % rhs) }
+        }
+
+        fn wrapping_add(self, rhs: Self) -> Self {
+            self + rhs
+        }
+
+        fn wrapping_sub(self, rhs: Self) -> Self {
+            self - rhs
+        }
+
+        fn wrapping_mul(self, rhs: Self) -> Self {
+            self * rhs
+        }
+
+        fn saturating_add(self, rhs: Self) -> Self {
+            self + rhs
+        }
+
+        fn saturating_sub(self, rhs: Self) -> Self {
+            self - rhs
+        }
+
+        fn saturating_mul(self, rhs: Self) -> Self {
+            self * rhs
+        }
+    }
+
+    impl Display for BigInt {
+        fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+            let digits = self.magnitude.to_string();
+            f.pad_integral(!self.negative, "", &digits)
+        }
+    }
+
+    impl FromStr for BigInt {
+        type Err = ParseBigIntError;
+
+        fn from_str(s: &str) -> Result<Self, Self::Err> {
+            match s.strip_prefix('-') {
+                Some(rest) => Ok(BigInt::from_parts(true, rest.parse()?)),
+                None => Ok(BigInt::from_parts(false, s.strip_prefix('+').unwrap_or(s).parse()?)),
+            }
+        }
+    }
+
+    /// inverse of `a` modulo `m` via the extended euclidean algorithm, `None` unless they're coprime
+    fn mod_inverse(a: u64, m: u64) -> Option<u64> {
+        let (mut old_r, mut r) = (a as i128, m as i128);
+        let (mut old_s, mut s) = (1i128, 0i128);
+        while r != 0 {
+            let q = old_r / r;
+            (old_r, r) = (r, old_r - q * r);
+            (old_s, s) = (s, old_s - q * s);
+        }
+        if old_r != 1 {
+            return None;
+        }
+        Some(old_s.rem_euclid(m as i128) as u64)
+    }
+
+    /// Integer modulo `M`, always kept in `[0, M)`.
+    /// Division only works for divisors coprime to `M`, so everywhere if `M` is prime.
+    #[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Default)]
+    pub struct ModInt<const M: u64> {
+        value: u64,
+    }
+
+    impl<const M: u64> ModInt<M> {
+        pub const fn new(value: u64) -> ModInt<M> {
+            ModInt { value: value % M }
+        }
+
+        pub fn value(&self) -> u64 {
+            self.value
+        }
+
+        pub fn pow(self, mut exp: u64) -> ModInt<M> {
+            let mut base = self;
+            let mut result = ModInt::one();
+            while exp > 0 {
+                if exp & 1 == 1 {
+                    result = result * base;
+                }
+                base = base * base;
+                exp >>= 1;
+            }
+            result
+        }
+
+        pub fn inverse(self) -> Option<ModInt<M>> {
+            mod_inverse(self.value, M).map(|value| ModInt { value })
+        }
+
+        /// `None` if `rhs` has no inverse
+        pub fn checked_div(self, rhs: ModInt<M>) -> Option<ModInt<M>> {
+            Some(self * rhs.inverse()?)
+        }
+    }
+
+    impl<const M: u64> From<u64> for ModInt<M> {
+        fn from(val: u64) -> ModInt<M> {
+            ModInt::new(val)
+        }
+    }
+
+    impl<const M: u64> From<i64> for ModInt<M> {
+        fn from(val: i64) -> ModInt<M> {
+            ModInt { value: (val as i128).rem_euclid(M as i128) as u64 }
+        }
+    }
+
+    impl<const M: u64> Zero for ModInt<M> {
+        fn zero() -> Self {
+            ModInt { value: 0 }
+        }
+    }
+
+    impl<const M: u64> One for ModInt<M> {
+        fn one() -> Self {
+            ModInt::new(1)
+        }
+    }
+
+    impl<const M: u64> Add for ModInt<M> {
+        type Output = Self;
+
+        fn add(self, rhs: Self) -> Self {
+            ModInt { value: ((self.value as u128 + rhs.value as u128) % M as u128) as u64 }
+        }
+    }
+
+    impl<const M: u64> Sub for ModInt<M> {
+        type Output = Self;
+
+        fn sub(self, rhs: Self) -> Self {
+            self + -rhs
+        }
+    }
+
+    impl<const M: u64> Mul for ModInt<M> {
+        type Output = Self;
+
+        fn mul(self, rhs: Self) -> Self {
+            ModInt { value: ((self.value as u128 * rhs.value as u128) % M as u128) as u64 }
+        }
+    }
+
+    impl<const M: u64> Div for ModInt<M> {
+        type Output = Self;
+
+        fn div(self, rhs: Self) -> Self {
+            self.checked_div(rhs).expect("Divisor not invertible")
+        }
+    }
+
+    impl<const M: u64> Neg for ModInt<M> {
+        type Output = Self;
+
+        fn neg(self) -> Self {
+            if self.value == 0 { self } else { ModInt { value: M - self.value } }
+        }
+    }
+
+    impl<const M: u64> Display for ModInt<M> {
+        fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+            self.value.fmt(f)
+        }
+    }
+
+    impl<const M: u64> Semiring for ModInt<M> {}
+
+    impl<const M: u64> Ring for ModInt<M> {}
+
+    impl<const M: u64> Field for ModInt<M> {
+        fn is_zero(&self) -> bool {
+            self.value == 0
+        }
+    }
+
+    /// Integer modulo a modulus only known at runtime.
+    ///
+    /// `zero()` and `one()` can't know the modulus, so they produce values with modulus 0.
+    /// Those are plain signed integers until they're combined with a value that has a modulus,
+    /// so `zero() - one()` is -1 and becomes `m - 1` once it meets modulus `m`. They also compare
+    /// equal to any value they reduce to, so `zero() == new(7, 7)`.
+    #[derive(Clone, Copy, Debug)]
+    pub struct DynModInt {
+        /// in `[0, modulus)`, or any integer while the modulus is still 0
+        value: i128,
+        modulus: u64,
+    }
+
+    impl DynModInt {
+        pub fn new(value: u64, modulus: u64) -> DynModInt {
+            if modulus == 0 {
+                panic!("Zero modulus");
+            }
+            DynModInt { value: (value % modulus) as i128, modulus }
+        }
+
+        pub fn from_signed(value: i64, modulus: u64) -> DynModInt {
+            if modulus == 0 {
+                panic!("Zero modulus");
+            }
+            DynModInt { value: (value as i128).rem_euclid(modulus as i128), modulus }
+        }
+
+        /// panics for a negative value that never got a modulus
+        pub fn value(&self) -> u64 {
+            u64::try_from(self.value).expect("DynModInt without a modulus")
+        }
+
+        pub fn modulus(&self) -> u64 {
+            self.modulus
+        }
+
+        pub fn pow(self, mut exp: u64) -> DynModInt {
+            let mut base = self;
+            let mut result = if self.modulus == 0 { DynModInt::one() } else { DynModInt::new(1, self.modulus) };
+            while exp > 0 {
+                if exp & 1 == 1 {
+                    result = result * base;
+                }
+                base = base * base;
+                exp >>= 1;
+            }
+            result
+        }
+
+        /// `None` if there is none, which without a modulus is everything but ±1
+        pub fn inverse(self) -> Option<DynModInt> {
+            if self.modulus == 0 {
+                return (self.value == 1 || self.value == -1).then_some(self);
+            }
+            mod_inverse(self.value as u64, self.modulus)
+                .map(|value| DynModInt { value: value as i128, modulus: self.modulus })
+        }
+
+        /// `None` if `rhs` has no inverse
+        pub fn checked_div(self, rhs: DynModInt) -> Option<DynModInt> {
+            let (_, b, modulus) = self.unify(rhs);
+            Some(self * DynModInt { value: b, modulus }.inverse()?)
+        }
+
+        /// modulus shared by both operands, with values reduced into it
+        fn unify(self, rhs: DynModInt) -> (i128, i128, u64) {
+            let modulus = match (self.modulus, rhs.modulus) {
+                (0, m) | (m, 0) => m,
+                (a, b) if a == b => a,
+                _ => panic!("Mismatched moduli"),
+            };
+            let reduce = |v: i128| if modulus == 0 { v } else { v.rem_euclid(modulus as i128) };
+            (reduce(self.value), reduce(rhs.value), modulus)
+        }
+    }
+
+    impl PartialEq for DynModInt {
+        fn eq(&self, other: &DynModInt) -> bool {
+            match (self.modulus, other.modulus) {
+                (a, b) if a == b => self.value == other.value,
+                (0, m) | (m, 0) => self.value.rem_euclid(m as i128) == other.value.rem_euclid(m as i128),
+                _ => false,
+            }
+        }
+    }
+
+    impl Eq for DynModInt {}
+
+    impl Hash for DynModInt {
+        /// A value without a modulus equals its residue in every modulus, and modulo 1 that is
+        /// always 0, so a constant is the only hash that agrees with `==`.
+        fn hash<H: Hasher>(&self, _: &mut H) {}
+    }
+
+    impl Zero for DynModInt {
+        fn zero() -> Self {
+            DynModInt { value: 0, modulus: 0 }
+        }
+    }
+
+    impl One for DynModInt {
+        fn one() -> Self {
+            DynModInt { value: 1, modulus: 0 }
+        }
+    }
+
+    impl Add for DynModInt {
+        type Output = Self;
+
+        fn add(self, rhs: Self) -> Self {
+            let (a, b, modulus) = self.unify(rhs);
+            let value = if modulus == 0 {
+                a.checked_add(b).expect("DynModInt overflow without a modulus")
+            } else {
+                (a + b) % modulus as i128
+            };
+            DynModInt { value, modulus }
+        }
+    }
+
+    impl Sub for DynModInt {
+        type Output = Self;
+
+        fn sub(self, rhs: Self) -> Self {
+            let (a, b, modulus) = self.unify(rhs);
+            let value = if modulus == 0 {
+                a.checked_sub(b).expect("DynModInt overflow without a modulus")
+            } else {
+                (a - b).rem_euclid(modulus as i128)
+            };
+            DynModInt { value, modulus }
+        }
+    }
+
+    impl Mul for DynModInt {
+        type Output = Self;
+
+        fn mul(self, rhs: Self) -> Self {
+            let (a, b, modulus) = self.unify(rhs);
+            let value = if modulus == 0 {
+                a.checked_mul(b).expect("DynModInt overflow without a modulus")
+            } else {
+                // both factors are below 2^64, so the product needs the unsigned width
+                ((a as u128 * b as u128) % modulus as u128) as i128
+            };
+            DynModInt { value, modulus }
+        }
+    }
+
+    impl Div for DynModInt {
+        type Output = Self;
+
+        fn div(self, rhs: Self) -> Self {
+            self.checked_div(rhs).expect("Divisor not invertible")
+        }
+    }
+
+    impl Neg for DynModInt {
         type Output = Self;
 
-        fn sub(self, other: Self) -> Self {
-            let mut result = self.rows.clone();
-            for y in 0..HEIGHT {
-                for x in 0..WIDTH {
-                    result[y][x] = result[y][x] - *other.get(x, y);
+        fn neg(self) -> Self {
+            DynModInt::zero() - self
+        }
+    }
+
+    impl Display for DynModInt {
+        fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+            self.value.fmt(f)
+        }
+    }
+
+    impl Semiring for DynModInt {}
+
+    impl Ring for DynModInt {}
+
+    impl Field for DynModInt {
+        fn is_zero(&self) -> bool {
+            self.value == 0
+        }
+    }
+
+    /// `n` as an element of `T`, built by doubling so it works for any type with a `One`
+    pub fn from_natural<T: Semiring + Copy>(n: u64) -> T {
+        let mut result = T::zero();
+        for bit in (0..u64::BITS - n.leading_zeros()).rev() {
+            result = result + result;
+            if n >> bit & 1 == 1 {
+                result = result + T::one();
+            }
+        }
+        result
+    }
+
+    /// Polynomial with coefficients in ascending order of degree, never with trailing zeros
+    #[derive(Clone, PartialEq, Eq, Hash, Debug)]
+    pub struct Polynomial<T> {
+        coeffs: Vec<T>,
+    }
+
+    impl<T: Copy + Zero + PartialEq> Polynomial<T> {
+        pub fn new(mut coeffs: Vec<T>) -> Polynomial<T> {
+            while coeffs.last().is_some_and(|&c| c == T::zero()) {
+                coeffs.pop();
+            }
+            Polynomial { coeffs }
+        }
+
+        pub fn constant(c: T) -> Polynomial<T> {
+            Polynomial::new(vec![c])
+        }
+
+        pub fn coeffs(&self) -> &[T] {
+            &self.coeffs
+        }
+
+        /// `None` for the zero polynomial
+        pub fn degree(&self) -> Option<usize> {
+            self.coeffs.len().checked_sub(1)
+        }
+    }
+
+    impl<T: Copy + Zero + One + PartialEq> Polynomial<T> {
+        /// the polynomial `x`
+        pub fn x() -> Polynomial<T> {
+            Polynomial::new(vec![T::zero(), T::one()])
+        }
+    }
+
+    impl<T: Semiring + Copy + PartialEq> Polynomial<T> {
+        pub fn eval(&self, x: T) -> T {
+            self.coeffs.iter().rev().fold(T::zero(), |acc, &c| acc * x + c)
+        }
+
+        pub fn scale(&self, factor: T) -> Polynomial<T> {
+            Polynomial::new(self.coeffs.iter().map(|&c| c * factor).collect())
+        }
+
+        pub fn derivative(&self) -> Polynomial<T> {
+            Polynomial::new(
+                self.coeffs.iter()
+                    .enumerate()
+                    .skip(1)
+                    .map(|(i, &c)| from_natural::<T>(i as u64) * c)
+                    .collect(),
+            )
+        }
+    }
+
+    impl<T: Field + PartialEq> Polynomial<T> {
+        /// the unique polynomial of degree < `points.len()` through all `(x, y)` points,
+        /// built from Newton's divided differences. Panics on repeated `x` values.
+        pub fn interpolate(points: &[(T, T)]) -> Polynomial<T> {
+            let mut diffs: Vec<T> = points.iter().map(|&(_, y)| y).collect();
+            for j in 1..points.len() {
+                for i in (j..points.len()).rev() {
+                    let dx = points[i].0 - points[i - j].0;
+                    if dx.is_zero() {
+                        panic!("Repeated interpolation point");
+                    }
+                    diffs[i] = (diffs[i] - diffs[i - 1]) / dx;
+                }
+            }
+
+            let mut result = Polynomial::new(Vec::new());
+            for i in (0..points.len()).rev() {
+                let factor = Polynomial::new(vec![T::zero() - points[i].0, T::one()]);
+                result = &(&result * &factor) + &Polynomial::constant(diffs[i]);
+            }
+            result
+        }
+
+        /// value at `x` of the interpolating polynomial through `points`, without building it (Lagrange form)
+        pub fn lagrange_eval(points: &[(T, T)], x: T) -> T {
+            let mut result = T::zero();
+            for (i, &(xi, yi)) in points.iter().enumerate() {
+                let mut term = yi;
+                for (j, &(xj, _)) in points.iter().enumerate() {
+                    if i != j {
+                        term = term * (x - xj) / (xi - xj);
+                    }
+                }
+                result = result + term;
+            }
+            result
+        }
+    }
+
+    impl<T: Field + PartialEq> Polynomial<T> {
+        /// quotient and remainder of polynomial long division, panics on a zero divisor
+        pub fn div_rem(&self, divisor: &Polynomial<T>) -> (Polynomial<T>, Polynomial<T>) {
+            let Some(divisor_degree) = divisor.degree() else { panic!("Division by zero polynomial") };
+            let lead = divisor.coeffs[divisor_degree];
+
+            let mut rem = self.coeffs.clone();
+            let mut quot = vec![T::zero(); rem.len().saturating_sub(divisor_degree)];
+            for i in (0..quot.len()).rev() {
+                let factor = rem[i + divisor_degree] / lead;
+                quot[i] = factor;
+                for (j, &d) in divisor.coeffs.iter().enumerate() {
+                    rem[i + j] = rem[i + j] - factor * d;
+                }
+            }
+            rem.truncate(divisor_degree);
+
+            (Polynomial::new(quot), Polynomial::new(rem))
+        }
+    }
+
+    /// Given `values[i] = p(i)` for a polynomial `p` of degree < `values.len()`, computes `p(n)`
+    /// from the forward differences `Σ Δ^k p(0) * C(n, k)`. Divisions are exact, so integer types work.
+    pub fn extrapolate<T>(values: &[T], n: u64) -> T
+    where
+        T: Ring + Copy + Div<Output = T>,
+    {
+        let mut diffs = values.to_vec();
+        let mut result = T::zero();
+        let mut binomial = T::one();
+        for k in 0..values.len() {
+            if k > 0 {
+                // C(n, k) vanishes for k > n
+                if k as u64 > n {
+                    break;
+                }
+                binomial = binomial * from_natural(n - k as u64 + 1) / from_natural(k as u64);
+                for i in 0..values.len() - k {
+                    diffs[i] = diffs[i + 1] - diffs[i];
                 }
             }
-            Matrix { rows: result }
+            result = result + diffs[0] * binomial;
         }
+        result
     }
 
-    impl<
-        const WIDTH: usize,
-        const HEIGHT: usize,
-        const OTHER_WIDTH: usize,
-        T: Add<Output = T> + Mul<Output = T> + Copy + Zero,
-    > Mul<Matrix<OTHER_WIDTH, WIDTH, T>> for Matrix<WIDTH, HEIGHT, T>
-    {
-        type Output = Matrix<OTHER_WIDTH, HEIGHT, T>;
+    impl<T: Copy + Zero + PartialEq + Add<Output = T>> Add<&Polynomial<T>> for &Polynomial<T> {
+        type Output = Polynomial<T>;
 
-        fn mul(self, rhs: Matrix<OTHER_WIDTH, WIDTH, T>) -> Self::Output {
-            let mut result = [[T::zero(); OTHER_WIDTH]; HEIGHT];
+        fn add(self, rhs: &Polynomial<T>) -> Polynomial<T> {
+            let len = max(self.coeffs.len(), rhs.coeffs.len());
+            Polynomial::new(
+                (0..len)
+                    .map(|i| {
+                        let a = self.coeffs.get(i).copied().unwrap_or(T::zero());
+                        a + rhs.coeffs.get(i).copied().unwrap_or(T::zero())
+                    })
+                    .collect(),
+            )
+        }
+    }
 
-            for ry in 0..HEIGHT {
-                for rx in 0..OTHER_WIDTH {
-                    for x in 0..WIDTH {
-                        result[ry][rx] = result[ry][rx] + (*self.get(x, ry) * *rhs.get(rx, x))
-                    }
+    impl<T: Copy + Zero + PartialEq + Sub<Output = T>> Sub<&Polynomial<T>> for &Polynomial<T> {
+        type Output = Polynomial<T>;
+
+        fn sub(self, rhs: &Polynomial<T>) -> Polynomial<T> {
+            let len = max(self.coeffs.len(), rhs.coeffs.len());
+            Polynomial::new(
+                (0..len)
+                    .map(|i| {
+                        let a = self.coeffs.get(i).copied().unwrap_or(T::zero());
+                        a - rhs.coeffs.get(i).copied().unwrap_or(T::zero())
+                    })
+                    .collect(),
+            )
+        }
+    }
+
+    impl<T: Semiring + Copy + PartialEq> Mul<&Polynomial<T>> for &Polynomial<T> {
+        type Output = Polynomial<T>;
+
+        fn mul(self, rhs: &Polynomial<T>) -> Polynomial<T> {
+            if self.coeffs.is_empty() || rhs.coeffs.is_empty() {
+                return Polynomial::new(Vec::new());
+            }
+
+            let mut coeffs = vec![T::zero(); self.coeffs.len() + rhs.coeffs.len() - 1];
+            for (i, &a) in self.coeffs.iter().enumerate() {
+                for (j, &b) in rhs.coeffs.iter().enumerate() {
+                    coeffs[i + j] = coeffs[i + j] + a * b;
                 }
             }
+            Polynomial::new(coeffs)
+        }
+    }
 
-            Matrix { rows: result }
+    impl<T: Copy + Zero + PartialEq + Add<Output = T>> Add for Polynomial<T> {
+        type Output = Polynomial<T>;
+
+        fn add(self, rhs: Polynomial<T>) -> Polynomial<T> {
+            &self + &rhs
         }
     }
 
-    impl<
-        const WIDTH: usize,
-        const HEIGHT: usize,
-        const OTHER_WIDTH: usize,
-        T: Add<Output = T> + Mul<Output = T> + Copy + Zero,
-    > Mul<&Matrix<OTHER_WIDTH, WIDTH, T>> for &Matrix<WIDTH, HEIGHT, T>
-    {
-        type Output = Matrix<OTHER_WIDTH, HEIGHT, T>;
+    impl<T: Copy + Zero + PartialEq + Sub<Output = T>> Sub for Polynomial<T> {
+        type Output = Polynomial<T>;
 
-        fn mul(self, rhs: &Matrix<OTHER_WIDTH, WIDTH, T>) -> Self::Output {
-            let mut result = [[T::zero(); OTHER_WIDTH]; HEIGHT];
+        fn sub(self, rhs: Polynomial<T>) -> Polynomial<T> {
+            &self - &rhs
+        }
+    }
 
-            for ry in 0..HEIGHT {
-                for rx in 0..OTHER_WIDTH {
-                    for x in 0..WIDTH {
-                        result[ry][rx] = result[ry][rx] + (*self.get(x, ry) * *rhs.get(rx, x))
-                    }
+    impl<T: Semiring + Copy + PartialEq> Mul for Polynomial<T> {
+        type Output = Polynomial<T>;
+
+        fn mul(self, rhs: Polynomial<T>) -> Polynomial<T> {
+            &self * &rhs
+        }
+    }
+
+    /// Linear recurrence `a[n] = Σ coeffs[i] * a[n - 1 - i]` along with its first `coeffs.len()` terms
+    #[derive(Clone, PartialEq, Debug)]
+    pub struct LinearRecurrence<T> {
+        coeffs: Vec<T>,
+        initial: Vec<T>,
+    }
+
+    impl<T: Field + PartialEq> LinearRecurrence<T> {
+        pub fn new(coeffs: Vec<T>, initial: Vec<T>) -> LinearRecurrence<T> {
+            if coeffs.len() != initial.len() {
+                panic!("Need one initial term per coefficient");
+            }
+            LinearRecurrence { coeffs, initial }
+        }
+
+        /// Shortest recurrence generating `seq`, found with Berlekamp–Massey.
+        /// A recurrence of order `L` is only pinned down by at least `2L` terms.
+        pub fn find(seq: &[T]) -> LinearRecurrence<T> {
+            // connection polynomials, seq[n] + Σ current[i] * seq[n - i] = 0 for i in 1..=order
+            let mut current = vec![T::one()];
+            let mut previous = vec![T::one()];
+            let mut order = 0;
+            let mut shift = 1;
+            let mut prev_discrepancy = T::one();
+
+            for n in 0..seq.len() {
+                let discrepancy = (1..=order).fold(seq[n], |acc, i| acc + current[i] * seq[n - i]);
+                if discrepancy.is_zero() {
+                    shift += 1;
+                    continue;
+                }
+
+                let factor = discrepancy / prev_discrepancy;
+                let before = current.clone();
+                if current.len() < previous.len() + shift {
+                    current.resize(previous.len() + shift, T::zero());
+                }
+                for (i, &p) in previous.iter().enumerate() {
+                    current[i + shift] = current[i + shift] - factor * p;
+                }
+
+                if 2 * order <= n {
+                    order = n + 1 - order;
+                    previous = before;
+                    prev_discrepancy = discrepancy;
+                    shift = 1;
+                } else {
+                    shift += 1;
                 }
             }
 
-            Matrix { rows: result }
+            current.resize(order + 1, T::zero());
+            LinearRecurrence {
+                coeffs: current[1..].iter().map(|&c| T::zero() - c).collect(),
+                initial: seq[..order].to_vec(),
+            }
         }
-    }
 
-    impl <const WIDTH: usize, const HEIGHT: usize, T: Display> Display for Matrix<WIDTH, HEIGHT, T> {
-        fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-            for y in 0..HEIGHT {
-                for x in 0..HEIGHT {
-                    write!(f, "{:>2}", self.get(x, y))?;
+        pub fn coeffs(&self) -> &[T] {
+            &self.coeffs
+        }
+
+        pub fn order(&self) -> usize {
+            self.coeffs.len()
+        }
+
+        /// `a[n]` in `O(L^2 log n)`, by reducing `x^n` modulo the characteristic polynomial
+        pub fn nth(&self, n: u64) -> T {
+            let order = self.order();
+            if n < order as u64 {
+                return self.initial[n as usize];
+            }
+            if order == 0 {
+                return T::zero();
+            }
+
+            // x^L - Σ coeffs[i] x^(L-1-i)
+            let mut characteristic: Vec<T> = self.coeffs.iter().rev().map(|&c| T::zero() - c).collect();
+            characteristic.push(T::one());
+            let characteristic = Polynomial::new(characteristic);
+
+            let mut result = Polynomial::constant(T::one());
+            let mut base = Polynomial::x().div_rem(&characteristic).1;
+            let mut exp = n;
+            while exp > 0 {
+                if exp & 1 == 1 {
+                    result = (&result * &base).div_rem(&characteristic).1;
+                }
+                exp >>= 1;
+                if exp > 0 {
+                    base = (&base * &base).div_rem(&characteristic).1;
                 }
-                writeln!(f)?;
             }
 
-            Ok(())
+            result.coeffs().iter()
+                .zip(&self.initial)
+                .fold(T::zero(), |acc, (&r, &a)| acc + r * a)
         }
     }
 
@@ -296,4 +3290,673 @@ pub mod algebra {
         assert_eq!(M2.rows, [[2], [1], [2]]);
         assert_eq!((Matrix::all_ones() * M2).rows, [[5]]);
     }
+
+    #[test]
+    fn test_rational_normalise() {
+        let r = Rational::new(6i64, -4);
+        assert_eq!((r.numer(), r.denom()), (-3, 2));
+        assert_eq!(Rational::new(0i64, -7), Rational::zero());
+        assert_eq!(Rational::<i8>::checked_new(i8::MIN, -1), None);
+        assert_eq!(Rational::<i8>::checked_new(1, 0), None);
+
+        let min = Rational::new(i8::MIN, 1);
+        assert_eq!((min.numer(), min.denom()), (i8::MIN, 1));
+        assert_eq!(Rational::<i8>::checked_new(i8::MIN, 2).map(|r| r.numer()), Some(-64));
+        assert_eq!(Rational::<i8>::checked_new(i8::MIN, -2).map(|r| r.numer()), Some(64));
+        assert_eq!(Rational::<i8>::checked_new(i8::MIN, i8::MIN), Some(Rational::one()));
+        assert_eq!(Rational::<i8>::checked_new(1, i8::MIN), None);
+        assert_eq!(Rational::from(i8::MIN).checked_mul(Rational::new(1, 2)).map(|r| r.numer()), Some(-64));
+    }
+
+    #[test]
+    fn test_rational_arith() {
+        let a = Rational::new(1i64, 6);
+        let b = Rational::new(3i64, 4);
+        assert_eq!(a + b, Rational::new(11, 12));
+        assert_eq!(a - b, Rational::new(-7, 12));
+        assert_eq!(a * b, Rational::new(1, 8));
+        assert_eq!(a / b, Rational::new(2, 9));
+        assert_eq!(-a, Rational::new(-1, 6));
+        assert_eq!(Rational::new(-7i64, 2).floor(), -4);
+        assert_eq!(Rational::new(-7i64, 2).ceil(), -3);
+
+        let big = Rational::new(i8::MAX, 2);
+        assert_eq!(big.checked_add(big), Some(Rational::from_integer(i8::MAX)));
+        assert_eq!(big.checked_add(Rational::new(1, 2)), Some(Rational::new(64, 1)));
+        assert_eq!(Rational::from_integer(i8::MAX).checked_add(Rational::new(1, 2)), None);
+        assert_eq!(Rational::new(5i8, 2).checked_add(Rational::new(83, -82)), Some(Rational::new(61, 41)));
+        assert_eq!(Rational::new(i8::MIN, 1).checked_add(Rational::new(-1, 2)), None);
+        assert_eq!(Rational::new(-127i8, 1).checked_add(Rational::new(-1, 1)), Some(Rational::from_integer(i8::MIN)));
+        assert_eq!(Rational::new(-127i8, 2).checked_add(Rational::new(-3, 4)), None);
+        assert_eq!(Rational::new(-61i8, 2).checked_add(Rational::new(3, 4)), Some(Rational::new(-119, 4)));
+        assert_eq!(big.checked_mul(Rational::new(2, 127)), Some(Rational::one()));
+        assert_eq!(big.checked_div(Rational::zero()), None);
+    }
+
+    #[test]
+    fn test_rational_ord() {
+        assert!(Rational::new(1i64, 3) < Rational::new(1, 2));
+        assert!(Rational::new(-1i64, 3) > Rational::new(-1, 2));
+        assert!(Rational::new(2i64, 1) > Rational::new(3, 2));
+        // cross-multiplying these would overflow an i8
+        assert!(Rational::new(100i8, 101) < Rational::new(101, 102));
+        assert!(Rational::new(-100i8, 101) > Rational::new(-101, 102));
+        assert_eq!(Rational::new(3i8, 6).cmp(&Rational::new(1, 2)), Ordering::Equal);
+    }
+
+    #[test]
+    fn test_rational_parse_display() {
+        assert_eq!("3/-6".parse(), Ok(Rational::new(-1i64, 2)));
+        assert_eq!(" 4 ".parse(), Ok(Rational::from_integer(4i64)));
+        assert_eq!("1/0".parse::<Rational<i64>>(), Err(ParseRationalError::ZeroDenominator));
+        assert_eq!("x/2".parse::<Rational<i64>>(), Err(ParseRationalError::Numerator));
+        assert_eq!(Rational::new(-2i64, 4).to_string(), "-1/2");
+        assert_eq!(Rational::new(8i64, 4).to_string(), "2");
+    }
+
+    #[test]
+    fn test_rational_matrix() {
+        let m = Matrix {
+            rows: [[Rational::new(1i64, 2), Rational::new(1, 3)]],
+        };
+        let product = m * Matrix::all_ones().transpose();
+        assert_eq!(product.rows, [[Rational::new(5, 6)]]);
+    }
+
+    #[test]
+    fn test_rref_rank() {
+        let r = |n: i64| Rational::from_integer(n);
+        let m = Matrix {
+            rows: [[r(1), r(2), r(3)], [r(2), r(4), r(6)], [r(1), r(0), r(1)]],
+        };
+        let (reduced, pivots) = m.rref();
+        assert_eq!(reduced.rows, [[r(1), r(0), r(1)], [r(0), r(1), r(1)], [r(0), r(0), r(0)]]);
+        assert_eq!(pivots, vec![0, 1]);
+        assert_eq!(m.rank(), 2);
+        assert_eq!(Matrix::<3, 1, f64>::zero().rank(), 0);
+    }
+
+    #[test]
+    fn test_determinant() {
+        let m = Matrix {
+            rows: [[2.0f64, -3.0, 1.0], [2.0, 0.0, -1.0], [1.0, 4.0, 5.0]],
+        };
+        assert!((m.determinant() - 49.0).abs() < 1e-9);
+
+        let m = Matrix {
+            rows: [[0i64, 2, 1], [3, -1, 2], [4, 0, 1]],
+        };
+        assert_eq!(m.determinant_bareiss(), 14);
+        assert_eq!(m.map(|&v| Rational::from_integer(v)).determinant(), Rational::from_integer(14));
+        assert_eq!(Matrix { rows: [[1i64, 2], [2, 4]] }.determinant_bareiss(), 0);
+    }
+
+    #[test]
+    fn test_inverse() {
+        let r = |n: i64, d: i64| Rational::new(n, d);
+        let m = Matrix {
+            rows: [[r(2, 1), r(1, 1)], [r(7, 1), r(4, 1)]],
+        };
+        let inv = m.inverse().unwrap();
+        assert_eq!(inv.rows, [[r(4, 1), r(-1, 1)], [r(-7, 1), r(2, 1)]]);
+        assert_eq!((&m * &inv).rows, [[r(1, 1), r(0, 1)], [r(0, 1), r(1, 1)]]);
+
+        let singular = Matrix { rows: [[1.0, 2.0], [2.0, 4.0]] };
+        assert!(singular.inverse().is_none());
+    }
+
+    #[test]
+    fn test_solve() {
+        let r = |n: i64| Rational::from_integer(n);
+
+        // x + y = 3, x - y = 1
+        let a = Matrix { rows: [[r(1), r(1)], [r(1), r(-1)]] };
+        match a.solve(&Matrix { rows: [[r(3)], [r(1)]] }) {
+            Solution::Unique(x) => assert_eq!(x.rows, [[r(2)], [r(1)]]),
+            _ => panic!("Expected unique solution"),
+        }
+
+        // x + y = 3, 2x + 2y = 5
+        let a = Matrix { rows: [[r(1), r(1)], [r(2), r(2)]] };
+        assert!(matches!(a.solve(&Matrix { rows: [[r(3)], [r(5)]] }), Solution::Inconsistent));
+
+        // x + y + z = 3, y - z = 1
+        let a = Matrix { rows: [[r(1), r(1), r(1)], [r(0), r(1), r(-1)]] };
+        match a.solve(&Matrix { rows: [[r(3)], [r(1)]] }) {
+            Solution::Parametric { particular, basis } => {
+                assert_eq!(particular.rows, [[r(2)], [r(1)], [r(0)]]);
+                assert_eq!(basis.len(), 1);
+                assert_eq!(basis[0].rows, [[r(-2)], [r(1)], [r(1)]]);
+            }
+            _ => panic!("Expected parametric solution"),
+        }
+    }
+
+    #[test]
+    fn test_bitvec() {
+        let mut v = BitVec::zeros(130);
+        v.set(0, true);
+        v.set(64, true);
+        v.flip(129);
+        assert_eq!(v.count_ones(), 3);
+        assert_eq!(v.iter_ones().collect::<Vec<_>>(), vec![0, 64, 129]);
+        v.xor_assign(&BitVec::from_bools(&[true; 130]));
+        assert_eq!(v.count_ones(), 127);
+        assert!(!v.get(64));
+    }
+
+    #[test]
+    fn test_bit_matrix_rank_null_space() {
+        let m = BitMatrix::from_bools(&[
+            vec![true, true, false],
+            vec![false, true, true],
+            vec![true, false, true],
+        ]);
+        assert_eq!(m.rank(), 2);
+        let null = m.null_space();
+        assert_eq!(null, vec![BitVec::from_bools(&[true, true, true])]);
+        assert!(m.mul_vec(&null[0]).is_zero());
+    }
+
+    #[test]
+    fn test_bit_matrix_min_weight_solution() {
+        // lights [.##.], buttons (3) (1,3) (2) (2,3) (0,2) (0,1)
+        let buttons: [&[usize]; 6] = [&[3], &[1, 3], &[2], &[2, 3], &[0, 2], &[0, 1]];
+        let mut m = BitMatrix::new(buttons.len(), 4);
+        for (x, lights) in buttons.iter().enumerate() {
+            for &y in lights.iter() {
+                m.set(x, y, true);
+            }
+        }
+        let target = BitVec::from_bools(&[false, true, true, false]);
+
+        let best = m.min_weight_solution(&target).unwrap();
+        assert_eq!(best.count_ones(), 2);
+        assert_eq!(m.mul_vec(&best), target);
+
+        let (particular, basis) = m.solve(&target).unwrap();
+        assert_eq!(m.mul_vec(&particular), target);
+        assert_eq!(basis.len(), 6 - m.rank());
+
+        let stuck = BitMatrix::from_bools(&[vec![true], vec![true]]);
+        assert!(stuck.solve(&BitVec::from_bools(&[true, false])).is_none());
+    }
+
+    #[test]
+    fn test_linear_program() {
+        let r = |n: i64| Rational::from_integer(n);
+
+        // maximise 3x + 2y with x + y <= 4, x + 3y <= 6, x <= 3
+        let mut lp = LinearProgram::new(vec![r(-3), r(-2)]);
+        lp.add_constraint(vec![r(1), r(1)], Relation::Le, r(4));
+        lp.add_constraint(vec![r(1), r(3)], Relation::Le, r(6));
+        lp.add_constraint(vec![r(1), r(0)], Relation::Le, r(3));
+        assert_eq!(lp.minimise(), Ok((r(-11), vec![r(3), r(1)])));
+
+        // x + y >= 2, x - y = 1 with minimal x + 2y
+        let mut lp = LinearProgram::new(vec![1.0f64, 2.0]);
+        lp.add_constraint(vec![1.0, 1.0], Relation::Ge, 2.0);
+        lp.add_constraint(vec![1.0, -1.0], Relation::Eq, 1.0);
+        let (value, x) = lp.minimise().unwrap();
+        assert!((value - 2.5).abs() < 1e-9);
+        assert!((x[0] - 1.5).abs() < 1e-9 && (x[1] - 0.5).abs() < 1e-9);
+
+        let mut lp = LinearProgram::new(vec![r(1)]);
+        lp.add_constraint(vec![r(1)], Relation::Le, r(-1));
+        assert_eq!(lp.minimise(), Err(LpError::Infeasible));
+
+        let mut lp = LinearProgram::new(vec![r(-1), r(0)]);
+        lp.add_constraint(vec![r(1), r(-1)], Relation::Le, r(1));
+        assert_eq!(lp.minimise(), Err(LpError::Unbounded));
+    }
+
+    #[test]
+    fn test_integer_program() {
+        let r = |n: i64| Rational::from_integer(n);
+
+        // counters {3,5,4,7}, buttons (3) (1,3) (2) (2,3) (0,2) (0,1), fewest total presses
+        let buttons: [&[usize]; 6] = [&[3], &[1, 3], &[2], &[2, 3], &[0, 2], &[0, 1]];
+        let targets = [3, 5, 4, 7];
+        let mut lp = LinearProgram::new(vec![r(1); buttons.len()]);
+        for (counter, &target) in targets.iter().enumerate() {
+            let coeffs = buttons.iter().map(|b| r(b.contains(&counter) as i64)).collect();
+            lp.add_constraint(coeffs, Relation::Eq, r(target));
+        }
+        let (value, presses) = lp.minimise_integer().unwrap();
+        assert_eq!(value, r(10));
+        assert_eq!(presses.iter().sum::<i64>(), 10);
+
+        // 2x = 1 has a rational but no integer solution
+        let mut lp = LinearProgram::new(vec![r(1)]);
+        lp.add_constraint(vec![r(2)], Relation::Eq, r(1));
+        assert_eq!(lp.minimise().map(|(v, _)| v), Ok(Rational::new(1, 2)));
+        assert_eq!(lp.minimise_integer(), Err(LpError::Infeasible));
+
+        // 2x - 2y = 1 has rational points arbitrarily far out but no integer one
+        let mut lp = LinearProgram::new(vec![r(0), r(0)]);
+        lp.add_constraint(vec![r(2), r(-2)], Relation::Eq, r(1));
+        assert_eq!(lp.minimise_integer(), Err(LpError::NodeLimit));
+    }
+
+    #[test]
+    fn test_biguint_arith() {
+        let a: BigUint = "123456789012345678901234567890".parse().unwrap();
+        let b: BigUint = "987654321098765432109876543210".parse().unwrap();
+        assert_eq!((&a + &b).to_string(), "1111111110111111111011111111100");
+        assert_eq!((&b - &a).to_string(), "864197532086419753208641975320");
+        assert_eq!((&a * &b).to_string(), "121932631137021795226185032733622923332237463801111263526900");
+        assert_eq!(a.checked_sub(&b), None);
+        assert_eq!(BigUint::from(2).pow(100).to_string(), "1267650600228229401496703205376");
+        assert_eq!(BigUint::from(u64::MAX).to_u64(), Some(u64::MAX));
+        assert_eq!((BigUint::from(u64::MAX) + BigUint::one()).to_u64(), None);
+        assert_eq!(BigUint::zero().to_string(), "0");
+        assert_eq!("12a".parse::<BigUint>(), Err(ParseBigIntError));
+    }
+
+    #[test]
+    fn test_biguint_div_rem() {
+        let a: BigUint = "121932631137021795226185032733622923332237463801111263526907".parse().unwrap();
+        let b: BigUint = "987654321098765432109876543210".parse().unwrap();
+        let (q, r) = a.div_rem(&b);
+        assert_eq!(q.to_string(), "123456789012345678901234567890");
+        assert_eq!(r.to_string(), "7");
+
+        // compare against u128 across limb boundaries
+        let values = [1u128, 7, 0xFFFF_FFFF, 1 << 32, (1 << 64) - 1, 1 << 64, 0x1234_5678_9ABC_DEF0_1234_5678, u128::MAX];
+        for &x in values.iter() {
+            for &y in values.iter() {
+                let big = |v: u128| BigUint::from((v >> 64) as u64) * BigUint::from(1 << 32).pow(2) + BigUint::from(v as u64);
+                let (q, r) = big(x).div_rem(&big(y));
+                assert_eq!((q, r), (big(x / y), big(x % y)), "{x} / {y}");
+            }
+        }
+
+        let a: BigUint = "1000000000000000000000000000000".parse().unwrap();
+        let b: BigUint = "300000000000000000000".parse().unwrap();
+        assert_eq!(a.gcd(&b).to_string(), "100000000000000000000");
+    }
+
+    #[test]
+    fn test_bigint() {
+        let a = BigInt::from(-7i64);
+        let b = BigInt::from(2i64);
+        assert_eq!(&a + &b, BigInt::from(-5i64));
+        assert_eq!(&b - &a, BigInt::from(9i64));
+        assert_eq!(&a * &b, BigInt::from(-14i64));
+        assert_eq!(a.div_rem(&b), (BigInt::from(-3i64), BigInt::from(-1i64)));
+        assert_eq!(a.pow(3), BigInt::from(-343i64));
+        assert_eq!(&a + &BigInt::from(7i64), BigInt::zero());
+        assert!(!(&a + &BigInt::from(7i64)).is_negative());
+        assert!(a < b);
+        assert_eq!("-123456789012345678901".parse::<BigInt>().unwrap().to_string(), "-123456789012345678901");
+        assert_eq!(format!("{:>5}", BigInt::from(-12i64)), "  -12");
+    }
+
+    #[test]
+    fn test_mod_int() {
+        type F = ModInt<7>;
+        assert_eq!(F::new(5) + F::new(4), F::new(2));
+        assert_eq!(F::new(2) - F::new(5), F::new(4));
+        assert_eq!(F::new(3) * F::new(5), F::new(1));
+        assert_eq!(F::new(3) / F::new(5), F::new(2));
+        assert_eq!(-F::new(3), F::new(4));
+        assert_eq!(F::from(-15i64), F::new(6));
+        assert_eq!(F::new(3).pow(6), F::one());
+        assert_eq!(ModInt::<6>::new(4).inverse(), None);
+
+        // near the top of the u64 range the intermediate products need the u128
+        type Big = ModInt<{ u64::MAX - 58 }>;
+        assert_eq!((Big::new(u64::MAX - 59) * Big::new(u64::MAX - 59)).value(), 1);
+    }
+
+    #[test]
+    fn test_dyn_mod_int() {
+        let a = DynModInt::new(5, 7);
+        assert_eq!(a + DynModInt::one(), DynModInt::new(6, 7));
+        assert_eq!(DynModInt::zero() - a, DynModInt::new(2, 7));
+        assert_eq!(a * DynModInt::from_signed(-1, 7), DynModInt::new(2, 7));
+        assert_eq!(DynModInt::one() / a, DynModInt::new(3, 7));
+        assert_eq!(a.pow(6), DynModInt::new(1, 7));
+
+        // values without a modulus stay exact integers until they adopt one
+        assert_eq!(-DynModInt::one() + DynModInt::new(0, 7), DynModInt::new(6, 7));
+        assert_eq!((DynModInt::zero() - DynModInt::one() - DynModInt::one()) * a, DynModInt::new(4, 7));
+
+        let swap = Matrix { rows: [[DynModInt::new(0, 7), DynModInt::new(1, 7)], [DynModInt::new(1, 7), DynModInt::new(0, 7)]] };
+        assert_eq!(swap.determinant(), DynModInt::new(6, 7));
+
+        // values without a modulus match their residues, so they work as field constants
+        assert_eq!(DynModInt::new(0, 7), DynModInt::zero());
+        assert_eq!(DynModInt::new(1, 7), DynModInt::one());
+        assert_eq!(DynModInt::new(6, 7), -DynModInt::one());
+        assert_ne!(DynModInt::new(1, 7), DynModInt::new(1, 11));
+        assert_eq!(a.pow(0).modulus(), 7);
+        assert_eq!(DynModInt::one().inverse(), Some(DynModInt::one()));
+        assert_eq!((DynModInt::one() + DynModInt::one()).inverse(), None);
+        assert_eq!(DynModInt::one().checked_div(DynModInt::zero()), None);
+
+        let m = |v: u64| DynModInt::new(v, 7);
+        let p = Polynomial::new(vec![m(3), m(1), m(0), m(7)]);
+        assert_eq!(p.degree(), Some(1));
+        // (x^2 + 1) = (x + 3)(x + 4) + 3 over Z/7
+        let (q, rem) = Polynomial::new(vec![m(1), m(0), m(1)]).div_rem(&Polynomial::new(vec![m(3), m(1)]));
+        assert_eq!(q, Polynomial::new(vec![m(4), m(1)]));
+        assert_eq!(rem, Polynomial::constant(m(3)));
+
+        let fib: Vec<_> = [0, 1, 1, 2, 3, 5, 8, 13].iter().map(|&v| DynModInt::new(v, 1_000_000_007)).collect();
+        let rec = LinearRecurrence::find(&fib);
+        assert_eq!(rec.coeffs(), &[DynModInt::one(), DynModInt::one()]);
+        assert_eq!(rec.nth(90), DynModInt::new(2880067194370816120 % 1_000_000_007, 1_000_000_007));
+    }
+
+    #[test]
+    #[should_panic(expected = "Mismatched moduli")]
+    fn test_dyn_mod_int_mismatch() {
+        let _ = DynModInt::new(1, 7) + DynModInt::new(1, 11);
+    }
+
+    #[test]
+    fn test_matrix_pow_mod() {
+        type P = ModInt<1_000_000_007>;
+        let fib = Matrix { rows: [[P::one(), P::one()], [P::one(), P::zero()]] };
+        assert_eq!(fib.pow(10).rows[0][1], P::new(55));
+        assert_eq!(fib.pow(1000).rows[0][1], P::new(517691607));
+        assert_eq!(fib.pow(0).rows, [[P::one(), P::zero()], [P::zero(), P::one()]]);
+
+        let solved = Matrix { rows: [[P::new(2), P::new(1)], [P::new(1), P::new(1)]] }.inverse().unwrap();
+        assert_eq!(solved.rows, [[P::one(), -P::one()], [-P::one(), P::new(2)]]);
+    }
+
+    #[test]
+    fn test_polynomial_arith() {
+        let p = Polynomial::new(vec![1i64, 2, 3]);
+        let q = Polynomial::new(vec![-1i64, 1]);
+        assert_eq!(p.eval(2), 17);
+        assert_eq!(&p + &q, Polynomial::new(vec![0, 3, 3]));
+        assert_eq!(&p - &p, Polynomial::new(vec![]));
+        assert_eq!((&p - &p).degree(), None);
+        assert_eq!(&p * &q, Polynomial::new(vec![-1, -1, -1, 3]));
+        assert_eq!(p.derivative(), Polynomial::new(vec![2, 6]));
+        assert_eq!(Polynomial::new(vec![1i64, 0, 0]).degree(), Some(0));
+        assert_eq!(from_natural::<i64>(1234567), 1234567);
+    }
+
+    #[test]
+    fn test_interpolate() {
+        let r = |n: i64| Rational::from_integer(n);
+        // x^3 - 2x + 1/2
+        let points: Vec<_> = [-1i64, 0, 2, 5]
+            .iter()
+            .map(|&x| (r(x), r(x * x * x - 2 * x) + Rational::new(1, 2)))
+            .collect();
+        let p = Polynomial::interpolate(&points);
+        assert_eq!(p.coeffs(), &[Rational::new(1, 2), r(-2), r(0), r(1)]);
+        assert_eq!(Polynomial::lagrange_eval(&points, r(10)), p.eval(r(10)));
+
+        type P = ModInt<1_000_000_007>;
+        let points = [(P::new(1), P::new(1)), (P::new(2), P::new(4)), (P::new(3), P::new(9))];
+        assert_eq!(Polynomial::lagrange_eval(&points, P::new(100_000)), P::new(10_000_000_000 % 1_000_000_007));
+    }
+
+    #[test]
+    fn test_extrapolate() {
+        assert_eq!(extrapolate(&[0i64, 3, 6, 9, 12, 15], 6), 18);
+        assert_eq!(extrapolate(&[1i64, 3, 6, 10, 15, 21], 6), 28);
+        assert_eq!(extrapolate(&[10i64, 13, 16, 21, 30, 45], 6), 68);
+        // triangle numbers, far ahead
+        assert_eq!(extrapolate(&[0i64, 1, 3, 6], 1_000_000), 500_000_500_000);
+        assert_eq!(extrapolate(&[5i64], 1_000), 5);
+        assert_eq!(extrapolate(&[0i64, 1, 3, 6], 2), 3);
+    }
+
+    #[test]
+    fn test_polynomial_div_rem() {
+        let r = |n: i64| Rational::from_integer(n);
+        let p = Polynomial::new(vec![r(-4), r(0), r(-2), r(1)]);
+        let d = Polynomial::new(vec![r(-3), r(1)]);
+        let (q, rem) = p.div_rem(&d);
+        assert_eq!(q, Polynomial::new(vec![r(3), r(1), r(1)]));
+        assert_eq!(rem, Polynomial::constant(r(5)));
+        assert_eq!(d.div_rem(&p), (Polynomial::new(vec![]), d.clone()));
+    }
+
+    #[test]
+    fn test_linear_recurrence() {
+        let r = |n: i64| Rational::from_integer(n);
+        let fib: Vec<_> = [0i64, 1, 1, 2, 3, 5, 8, 13].iter().map(|&n| r(n)).collect();
+        let rec = LinearRecurrence::find(&fib);
+        assert_eq!(rec.coeffs(), &[r(1), r(1)]);
+        assert_eq!(rec.nth(7), r(13));
+        assert_eq!(rec.nth(90), r(2880067194370816120));
+
+        // a[n] = 2a[n-1] - a[n-3], with some noise in front of the pattern
+        let seq: Vec<_> = [3i64, 1, 4, 7, 13, 22, 37, 61, 100].iter().map(|&n| r(n)).collect();
+        let rec = LinearRecurrence::find(&seq);
+        assert!(rec.order() <= 4);
+        for (n, &v) in seq.iter().enumerate() {
+            assert_eq!(rec.nth(n as u64), v);
+        }
+
+        type P = ModInt<1_000_000_007>;
+        let powers: Vec<P> = (0..10).map(|n| P::new(3).pow(n)).collect();
+        let rec = LinearRecurrence::find(&powers);
+        assert_eq!(rec.coeffs(), &[P::new(3)]);
+        assert_eq!(rec.nth(1_000_000_000_000), P::new(3).pow(1_000_000_000_000));
+
+        let zeros = LinearRecurrence::find(&[P::zero(); 5]);
+        assert_eq!(zeros.order(), 0);
+        assert_eq!(zeros.nth(12), P::zero());
+    }
+
+    #[test]
+    fn test_reductions() {
+        let m = Matrix { rows: [[3, -1, 4], [1, 5, -9]] };
+        assert_eq!(m.sum(), 3);
+        assert_eq!(m.product(), 540);
+        assert_eq!(m.count(|&v| v > 0), 4);
+        assert_eq!(m.fold(0, |acc, &v| acc.max(v)), 5);
+        assert_eq!(m.min(), Some(-9));
+        assert_eq!(m.max(), Some(5));
+        assert_eq!(m.argmax(), Some((1, 1)));
+        assert_eq!(m.argmin(), Some((2, 1)));
+        assert_eq!(Matrix::<0, 0, i32> { rows: [] }.argmax(), None);
+    }
+
+    #[test]
+    fn test_elementwise() {
+        let a = Matrix { rows: [[1, 2], [3, 4]] };
+        let b = Matrix { rows: [[5, 6], [7, 8]] };
+        assert_eq!(a.hadamard(&b).rows, [[5, 12], [21, 32]]);
+        assert_eq!(a.zip_with(&b, |x, y| y - x).rows, [[4, 4], [4, 4]]);
+        assert_eq!((Matrix { rows: [[1, 2], [3, 4]] } * 3).rows, [[3, 6], [9, 12]]);
+        assert_eq!((Matrix { rows: [[1, 2], [3, 4]] } + 1).rows, [[2, 3], [4, 5]]);
+        assert_eq!((-Matrix { rows: [[1, -2]] }).rows, [[-1, 2]]);
+
+        let even = a.mask(|v| v % 2 == 0);
+        assert_eq!(even.rows, [[false, true], [false, true]]);
+        assert_eq!(Matrix::select(&even, &a, &b).rows, [[5, 2], [7, 4]]);
+        assert_eq!(a.masked(&even, 0).rows, [[0, 2], [0, 4]]);
+        assert_eq!(even.indicator::<i16>().sum(), 2);
+    }
+
+    #[test]
+    fn test_index_and_iteration() {
+        let mut m = Matrix { rows: [[1, 2, 3], [4, 5, 6]] };
+        assert_eq!(m[(2, 0)], 3);
+        m[(0, 1)] = 7;
+        *m.get_mut(1, 1) += 1;
+        assert_eq!(m.rows, [[1, 2, 3], [7, 6, 6]]);
+
+        assert_eq!(m.iter_rows().map(|row| row.iter().sum::<i32>()).collect::<Vec<_>>(), vec![6, 19]);
+        assert_eq!(m.iter_cols().map(|col| col.copied().collect()).collect::<Vec<Vec<_>>>(), vec![vec![1, 7], vec![2, 6], vec![3, 6]]);
+        assert_eq!(m.iter_positions().nth(4), Some((1, 1, &6)));
+        assert_eq!(m.iter_positions().count(), 6);
+    }
+
+    #[test]
+    fn test_neighbours() {
+        let m = Matrix::<3, 2, u8>::default();
+        assert_eq!(m.neighbours4(0, 0).collect::<Vec<_>>(), vec![(1, 0), (0, 1)]);
+        assert_eq!(m.neighbours4(1, 1).collect::<Vec<_>>(), vec![(1, 0), (0, 1), (2, 1)]);
+        assert_eq!(m.neighbours8(2, 1).collect::<Vec<_>>(), vec![(1, 0), (2, 0), (1, 1)]);
+        assert_eq!(m.neighbours8(1, 0).count(), 5);
+    }
+
+    #[test]
+    fn test_matrix_as_key() {
+        use std::collections::HashSet;
+
+        let a = Matrix { rows: [[1u8, 0], [0, 1]] };
+        let mut seen = HashSet::new();
+        assert!(seen.insert(a.clone()));
+        assert!(!seen.insert(Matrix { rows: [[1, 0], [0, 1]] }));
+        assert_eq!(Matrix::<40, 40, u8>::default(), Matrix { rows: [[0; 40]; 40] });
+        assert_eq!(format!("{:?}", Matrix { rows: [[1]] }), "Matrix { rows: [[1]] }");
+    }
+
+    #[test]
+    fn test_rotate_flip() {
+        let m = Matrix { rows: [[1, 2, 3], [4, 5, 6]] };
+        assert_eq!(m.rotate_cw().rows, [[4, 1], [5, 2], [6, 3]]);
+        assert_eq!(m.rotate_ccw().rows, [[3, 6], [2, 5], [1, 4]]);
+        assert_eq!(m.rotate_180().rows, [[6, 5, 4], [3, 2, 1]]);
+        assert_eq!(m.flip_h().rows, [[3, 2, 1], [6, 5, 4]]);
+        assert_eq!(m.flip_v().rows, [[4, 5, 6], [1, 2, 3]]);
+        assert_eq!(m.rotate_cw().rotate_ccw(), m);
+    }
+
+    #[test]
+    fn test_symmetries() {
+        let m = Matrix { rows: [[1, 2], [3, 4]] };
+        let all: Vec<_> = m.symmetries().collect();
+        assert_eq!(all.len(), 8);
+        assert_eq!(all[0], m);
+
+        let line = Matrix { rows: [[1, 1, 1], [0, 0, 0], [0, 0, 0]] };
+        assert_eq!(line.symmetries().count(), 4);
+        assert_eq!(Matrix { rows: [[7; 3]; 3] }.symmetries().count(), 1);
+    }
+
+    #[test]
+    fn test_views() {
+        let m = Matrix { rows: [[1, 2, 3], [4, 5, 6], [7, 8, 9]] };
+        let view = m.view(1, 1, 2, 2);
+        assert_eq!(*view.get(0, 0), 5);
+        assert_eq!(*view.get(1, 1), 9);
+        assert_eq!(view.iter_rows().collect::<Vec<_>>(), vec![&[5, 6][..], &[8, 9][..]]);
+        assert_eq!(view.iter_positions().map(|(_, _, &v)| v).sum::<i32>(), 28);
+
+        let sums: Vec<i32> = m.windows(2, 2).map(|w| w.iter_positions().map(|(_, _, &v)| v).sum()).collect();
+        assert_eq!(sums, vec![12, 16, 24, 28]);
+        assert_eq!(m.windows(4, 1).count(), 0);
+        assert_eq!(m.windows(3, 3).next().unwrap().offset(), (0, 0));
+    }
+
+    #[test]
+    fn test_mul_in_place() {
+        let a = Matrix { rows: [[1, 2], [3, 4]] };
+        let b = Matrix { rows: [[0, 1], [1, 0]] };
+
+        let mut m = a.clone();
+        m *= &b;
+        assert_eq!(m, &a * &b);
+        assert_eq!(m.rows, [[2, 1], [4, 3]]);
+
+        m += &a;
+        assert_eq!(m.rows, [[3, 3], [7, 7]]);
+        m -= a.clone();
+        assert_eq!(m.rows, [[2, 1], [4, 3]]);
+    }
+
+    #[test]
+    fn test_mul_widening() {
+        let m = Matrix { rows: [[200u8, 200], [1, 2]] };
+        let v = Matrix { rows: [[200u8], [100]] };
+        assert_eq!(m.mul_widening::<1, u32>(&v).rows, [[60000], [400]]);
+
+        let big = Matrix { rows: [[i16::MAX; 3]] };
+        assert_eq!(big.mul_widening::<1, i64>(&Matrix::all_ones().transpose()).rows, [[3 * i16::MAX as i64]]);
+    }
+
+    #[test]
+    fn test_numeric_traits() {
+        fn sum_of_squares<T: Semiring + Copy>(xs: &[T]) -> T {
+            xs.iter().fold(T::zero(), |acc, &x| acc + x * x)
+        }
+
+        assert_eq!(sum_of_squares(&[1u8, 2, 3]), 14);
+        assert_eq!(sum_of_squares(&[0.5f64, 1.5]), 2.5);
+        assert_eq!(sum_of_squares(&[ModInt::<7>::from(3u64), ModInt::from(4u64)]), ModInt::from(4u64));
+
+        assert_eq!(Semiring::checked_add(u8::MAX, 1), None);
+        assert_eq!(Ring::checked_sub(0u32, 1), None);
+        assert_eq!(Ring::checked_neg(i32::MIN), None);
+        assert_eq!(Integer::checked_div(1i64, 0), None);
+        assert_eq!(Integer::saturating_mul(100i8, 2), i8::MAX);
+        assert_eq!(Integer::wrapping_add(u8::MAX, 2), 1);
+
+        assert!(Field::is_zero(&Rational::<i64>::zero()));
+        assert_eq!(Semiring::checked_mul(Rational::from(i64::MAX), Rational::from(2)), None);
+    }
+
+    #[test]
+    fn test_checked_matrix_ops() {
+        let small = Matrix::from_arr([[1i8, 2], [3, 4]]);
+        assert_eq!(small.checked_mul(&small), Some(&small * &small));
+        assert_eq!(small.checked_pow(3), Some(small.pow(3)));
+        assert_eq!(small.checked_add(&small), Some(small.clone() + small.clone()));
+        assert_eq!(small.checked_sub(&small), Some(Matrix { rows: [[0; 2]; 2] }));
+        assert_eq!(small.checked_sum(), Some(10));
+
+        let big = Matrix::from_arr([[100i8, 100], [100, 100]]);
+        assert_eq!(big.checked_add(&big), None);
+        assert_eq!(big.checked_mul(&small), None);
+        assert_eq!(small.checked_pow(5), None);
+        assert_eq!(big.checked_sum(), None);
+        assert_eq!((-big.clone()).checked_sub(&big), None);
+
+        let kernel = Matrix::from_arr([[1i8, 1, 1], [1, 1, 1], [1, 1, 1]]);
+        assert_eq!(small.checked_convolve(&kernel), Some(small.convolve_0(&kernel)));
+        assert_eq!(small.convolve_0(&kernel).rows, [[10, 10], [10, 10]]);
+        assert_eq!(big.checked_convolve(&kernel), None);
+    }
+
+    #[test]
+    fn test_square_utilities() {
+        let id: Matrix<3, 3, i32> = Matrix::identity();
+        assert_eq!(id.trace(), 3);
+        assert!(id.is_symmetric());
+        assert_eq!(Matrix::<3, 2, i32>::identity().rows, [[1, 0, 0], [0, 1, 0]]);
+        assert_eq!(Matrix::<2, 3, i32>::diagonal(&[4, 5]).rows, [[4, 0], [0, 5], [0, 0]]);
+        assert_eq!(Matrix::<2, 2, u8>::fill(7).rows, [[7, 7], [7, 7]]);
+
+        let m = Matrix::from_arr([[1, 2], [3, 4]]);
+        assert_eq!(m.trace(), 5);
+        assert!(!m.is_symmetric());
+        assert!((&m * &m.transpose()).is_symmetric());
+        assert_eq!(m.pow(0), Matrix::identity());
+
+        let k: Matrix<4, 4, i32> = m.kronecker(&Matrix::<2, 2, i32>::identity());
+        assert_eq!(k.rows, [[1, 0, 2, 0], [0, 1, 0, 2], [3, 0, 4, 0], [0, 3, 0, 4]]);
+        let k: Matrix<2, 4, i32> = Matrix::from_arr([[1], [2]]).kronecker(&m);
+        assert_eq!(k.rows, [[1, 2], [3, 4], [2, 4], [6, 8]]);
+    }
+
+    #[test]
+    fn test_stack_and_resize() {
+        let a = Matrix::from_arr([[1, 2], [3, 4]]);
+        let b = Matrix::from_arr([[5], [6]]);
+        let h: Matrix<3, 2, i32> = a.hstack(&b);
+        assert_eq!(h.rows, [[1, 2, 5], [3, 4, 6]]);
+        let v: Matrix<2, 3, i32> = a.vstack(&Matrix::from_arr([[7, 8]]));
+        assert_eq!(v.rows, [[1, 2], [3, 4], [7, 8]]);
+
+        let grown: Matrix<3, 3, i32> = a.resize(0);
+        assert_eq!(grown.rows, [[1, 2, 0], [3, 4, 0], [0, 0, 0]]);
+        let shrunk: Matrix<1, 2, i32> = h.resize(0);
+        assert_eq!(shrunk.rows, [[1], [3]]);
+
+        let padded: Matrix<4, 3, i32> = a.pad(1, 1, -1);
+        assert_eq!(padded.rows, [[-1, -1, -1, -1], [-1, 1, 2, -1], [-1, 3, 4, -1]]);
+    }
 }
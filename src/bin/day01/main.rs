@@ -3,9 +3,12 @@ const MAX_POS: isize = 100;
 
 const INPUT_A: &'static str = include_str!("../../../resources/day01/input_a.txt");
 
+use aoc_2025::algebra::algebra::ModInt;
 use aoc_2025::harness::run_timed;
 use aoc_2025::util;
 
+type Dial = ModInt<{ MAX_POS as u64 }>;
+
 fn main() {
     run_timed(solve_a);
     run_timed(solve_b);
@@ -40,9 +43,9 @@ fn get_zero_passes(input: &str) -> impl Iterator<Item=usize> {
 fn get_intermediate_position(input: &str) -> impl Iterator<Item=isize> {
     input.lines()
         .map(line_to_int)
-        .scan(INITIAL_POS, |pos, shift| {
-            *pos = util::posmod(*pos + shift, MAX_POS);
-            Some(*pos)
+        .scan(Dial::new(INITIAL_POS as u64), |pos, shift| {
+            *pos = *pos + Dial::from(shift as i64);
+            Some(pos.value() as isize)
         })
 }
 
@@ -1,6 +1,8 @@
+use aoc_2025::algebra::algebra::Semiring;
 use aoc_2025::harness::run_timed;
 use aoc_2025::util::{gcd, triangle};
 use std::cmp::min;
+use std::ops::{Div, Sub};
 
 const INPUT_A: &'static str = include_str!("../../../resources/day02/input_a.txt");
 
@@ -11,20 +13,31 @@ fn main() {
 
 fn solve_b() -> u64 {
     parse_range_list(INPUT_A)
-        .map(|(lo, hi)| sum_iterwords_in_range(lo, hi))
+        .map(|(lo, hi)| sum_iterwords_in_range::<u64>(lo, hi))
         .sum()
 }
 
 fn solve_a() -> u64 {
     parse_range_list(INPUT_A)
-        .map(|(lo, hi)| sum_copywords_in_range(lo, hi))
+        .map(|(lo, hi)| sum_copywords_in_range::<u64>(lo, hi))
         .sum()
 }
 
-fn sum_iterwords_in_range(low: u64, high: u64) -> u64 {
+/// Number type the range sums are accumulated in, `BigUint` once they outgrow `u64`
+trait RangeSum:
+    Semiring + Clone + From<u64> + Sub<Output = Self> + Div<Output = Self>
+{
+}
+
+impl<T> RangeSum for T where
+    T: Semiring + Clone + From<u64> + Sub<Output = T> + Div<Output = T>
+{
+}
+
+fn sum_iterwords_in_range<T: RangeSum>(low: u64, high: u64) -> T {
     let mut current_low = low;
     let mut current_high = min(high, next_power_of_ten(current_low) - 1);
-    let mut sum = 0;
+    let mut sum = T::zero();
 
     loop {
         let num_digits = current_low.ilog10() + 1;
@@ -37,11 +50,11 @@ fn sum_iterwords_in_range(low: u64, high: u64) -> u64 {
                 continue;
             }
 
-            sum += sum_bs_iterwords_in_range_fixed(current_low, current_high, block_size);
+            sum = sum + sum_bs_iterwords_in_range_fixed(current_low, current_high, block_size);
 
             // quick and dirty hack for input sizes
             for &other in &divisors {
-                sum -= sum_bs_iterwords_in_range_fixed(
+                sum = sum - sum_bs_iterwords_in_range_fixed(
                     current_low,
                     current_high,
                     gcd(block_size, other),
@@ -60,10 +73,10 @@ fn sum_iterwords_in_range(low: u64, high: u64) -> u64 {
     sum
 }
 
-fn sum_bs_iterwords_in_range_fixed(low: u64, high: u64, block_size: u32) -> u64 {
+fn sum_bs_iterwords_in_range_fixed<T: RangeSum>(low: u64, high: u64, block_size: u32) -> T {
     let num_digits = low.ilog10() + 1;
     if num_digits % block_size != 0 {
-        return 0;
+        return T::zero();
     }
 
     let coeff = get_coeff(num_digits, block_size);
@@ -77,7 +90,7 @@ fn sum_bs_iterwords_in_range_fixed(low: u64, high: u64, block_size: u32) -> u64
         end_prefix -= 1;
     }
 
-    coeff * (triangle(end_prefix) - triangle(start_prefix - 1))
+    T::from(coeff) * (triangle(T::from(end_prefix)) - triangle(T::from(start_prefix - 1)))
 }
 
 fn get_nth_infix(val: u64, block_size: u32, n: u32) -> u64 {
@@ -101,10 +114,10 @@ fn get_coeff(digits: u32, block_size: u32) -> u64 {
     sum
 }
 
-fn sum_copywords_in_range(low: u64, high: u64) -> u64 {
+fn sum_copywords_in_range<T: RangeSum>(low: u64, high: u64) -> T {
     let mut current_low = low;
     let mut current_high = min(high, next_power_of_ten(current_low) - 1);
-    let mut sum = 0;
+    let mut sum = T::zero();
 
     loop {
         if current_low.ilog10() % 2 != 0 {
@@ -121,7 +134,7 @@ fn sum_copywords_in_range(low: u64, high: u64) -> u64 {
             }
 
             let coeff = 10u64.pow((current_low.ilog10() + 1) / 2) + 1;
-            sum += coeff as u64 * (triangle(end_prefix) - triangle(start_prefix - 1));
+            sum = sum + T::from(coeff) * (triangle(T::from(end_prefix)) - triangle(T::from(start_prefix - 1)));
         }
 
         if current_high == high {
@@ -201,10 +214,10 @@ fn test_get_prefix_and_suffix() {
 
 #[test]
 fn test_sum_copywords_in_range() {
-    assert_eq!(sum_copywords_in_range(11, 11), 11);
-    assert_eq!(sum_copywords_in_range(11, 22), 33);
-    assert_eq!(sum_copywords_in_range(1, 100), 495);
-    assert_eq!(sum_copywords_in_range(80, 1211), 2308);
+    assert_eq!(sum_copywords_in_range::<u64>(11, 11), 11);
+    assert_eq!(sum_copywords_in_range::<u64>(11, 22), 33);
+    assert_eq!(sum_copywords_in_range::<u64>(1, 100), 495);
+    assert_eq!(sum_copywords_in_range::<u64>(80, 1211), 2308);
 }
 
 #[test]
@@ -228,19 +241,19 @@ fn test_get_coeff() {
 
 #[test]
 fn test_sum_iterwords_in_range() {
-    assert_eq!(sum_iterwords_in_range(11, 22), 33);
-    assert_eq!(sum_iterwords_in_range(99, 115), 210);
-    assert_eq!(sum_iterwords_in_range(998, 1012), 999 + 1010);
-    assert_eq!(sum_iterwords_in_range(1188511880, 1188511890), 1188511885);
-    assert_eq!(sum_iterwords_in_range(222220, 222224), 222222);
-    assert_eq!(sum_iterwords_in_range(1698522, 1698528), 0);
-    assert_eq!(sum_iterwords_in_range(446443, 446449), 446446);
-    assert_eq!(sum_iterwords_in_range(38593856, 38593862), 38593859);
-    assert_eq!(sum_iterwords_in_range(565653, 565659), 565656);
-    assert_eq!(sum_iterwords_in_range(824824821, 824824827), 824824824);
-    assert_eq!(sum_iterwords_in_range(2121212118,2121212124), 2121212121);
+    assert_eq!(sum_iterwords_in_range::<u64>(11, 22), 33);
+    assert_eq!(sum_iterwords_in_range::<u64>(99, 115), 210);
+    assert_eq!(sum_iterwords_in_range::<u64>(998, 1012), 999 + 1010);
+    assert_eq!(sum_iterwords_in_range::<u64>(1188511880, 1188511890), 1188511885);
+    assert_eq!(sum_iterwords_in_range::<u64>(222220, 222224), 222222);
+    assert_eq!(sum_iterwords_in_range::<u64>(1698522, 1698528), 0);
+    assert_eq!(sum_iterwords_in_range::<u64>(446443, 446449), 446446);
+    assert_eq!(sum_iterwords_in_range::<u64>(38593856, 38593862), 38593859);
+    assert_eq!(sum_iterwords_in_range::<u64>(565653, 565659), 565656);
+    assert_eq!(sum_iterwords_in_range::<u64>(824824821, 824824827), 824824824);
+    assert_eq!(sum_iterwords_in_range::<u64>(2121212118,2121212124), 2121212121);
     assert_eq!(
-        sum_iterwords_in_range(11111_11111_11, 11111_11111_11),
+        sum_iterwords_in_range::<u64>(11111_11111_11, 11111_11111_11),
         11111_11111_11
     );
 }
@@ -249,7 +262,24 @@ fn test_sum_iterwords_in_range() {
 fn test_solve_b_example() {
     let input = "11-22,95-115,998-1012,1188511880-1188511890,222220-222224,1698522-1698528,446443-446449,38593856-38593862,565653-565659,824824821-824824827,2121212118-2121212124";
     let result: u64 = parse_range_list(input)
-        .map(|(lo, hi)| sum_iterwords_in_range(lo, hi))
+        .map(|(lo, hi)| sum_iterwords_in_range::<u64>(lo, hi))
         .sum();
     assert_eq!(result, 4174379265);
 }
+
+#[test]
+fn test_range_sums_beyond_u64() {
+    use aoc_2025::algebra::algebra::BigUint;
+
+    let input = "11-22,95-115,998-1012,1188511880-1188511890";
+    for (lo, hi) in parse_range_list(input) {
+        assert_eq!(
+            sum_iterwords_in_range::<BigUint>(lo, hi).to_u64(),
+            Some(sum_iterwords_in_range::<u64>(lo, hi))
+        );
+    }
+
+    // every 18 digit copyword, the total no longer fits in a u64
+    let sum: BigUint = sum_copywords_in_range(10u64.pow(17), 10u64.pow(18) - 1);
+    assert_eq!(sum.to_string(), "495000000044999999550000000");
+}
@@ -11,7 +11,7 @@ fn main() {
 
 fn solve_a() -> i16 {
     let removable_grid = get_removable(&matrix_from_input::<136,136>(INPUT_A));
-    sum(&removable_grid)
+    removable_grid.sum()
 }
 
 fn solve_b() -> i32 {
@@ -19,11 +19,11 @@ fn solve_b() -> i32 {
     let mut tot_sum = 0;
     loop {
         let removable = get_removable(&grid);
-        let new = sum(&removable);
+        let new = removable.sum();
         if new == 0 { break; }
 
         tot_sum += new as i32;
-        grid = grid - removable;
+        grid -= &removable;
     }
 
     tot_sum
@@ -41,10 +41,6 @@ fn get_removable<const W: usize, const H: usize>(grid: &Matrix<W, H, i16>) -> Ma
         .map(|&val| if val <= 0 { 0 } else { 1 })
 }
 
-fn sum(m: &Matrix<136, 136, i16>) -> i16 {
-    *(Matrix::all_ones() * (m * &Matrix::all_ones().transpose())).get(0, 0)
-}
-
 
 fn matrix_from_input<const W: usize, const H: usize>(input: &str) -> Matrix<W, H, i16> {
     let as_vec = input.lines()
@@ -60,3 +56,20 @@ fn matrix_from_input<const W: usize, const H: usize>(input: &str) -> Matrix<W, H
     Matrix::from_vec(as_vec)
 }
 
+
+#[test]
+fn test_removable_example() {
+    let input = "\
+..@@.@@@@.
+@@@.@.@.@@
+@@@@@.@.@@
+@.@@@@..@.
+@@.@@@@.@@
+.@@@@@@@.@
+.@.@.@.@@@
+@.@@@.@@@@
+.@@@@@@@@.
+@.@.@@@.@.";
+    let grid = matrix_from_input::<10, 10>(input);
+    assert_eq!(get_removable(&grid).sum(), 13);
+}
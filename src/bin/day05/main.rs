@@ -1,4 +1,4 @@
-use std::ops::Range;
+use std::ops::RangeInclusive;
 use aoc_2025::combinatorics::combinatorics::RangeSet;
 use aoc_2025::harness::run_timed;
 
@@ -16,19 +16,20 @@ fn solve_b() -> u64 {
 }
 
 fn solve_a() -> usize {
-    let (ranges, ids) = parse_input(INPUT_A);
+    let (ranges, mut ids) = parse_input(INPUT_A);
     let set = RangeSet::from_vec(ranges);
-    ids.into_iter().filter(|id| set.contains(*id)).count()
+    ids.sort_unstable();
+    set.contains_sorted_batch(&ids).into_iter().filter(|&fresh| fresh).count()
 }
 
-fn parse_range(line: &str) -> Range<u64> {
+fn parse_range(line: &str) -> RangeInclusive<u64> {
     let (start, end) = line.split_once("-").expect("Failed to split range");
     let start = start.parse().expect("Failed to parse start");
-    let end: u64 = end.parse().expect("Failed to parse end");
-    start..(end+1)
+    let end = end.parse().expect("Failed to parse end");
+    start..=end
 }
 
-fn parse_input(input: &str) -> (Vec<Range<u64>>, Vec<u64>) {
+fn parse_input(input: &str) -> (Vec<RangeInclusive<u64>>, Vec<u64>) {
     let (ranges, ids) = input.split_once("\n\n").expect("Failed to split input in half");
     let ranges = ranges.lines().map(parse_range).collect();
     let ids = ids.lines().map(|s| s.parse().expect("Failed to parse id")).collect();
@@ -41,13 +42,14 @@ fn test_parse_input() {
     let input = "1-2
 4-10
 10000-10000000
+18446744073709551610-18446744073709551615
 
 1
 70
 981
 ";
     let (ranges, ids) = parse_input(input);
-    assert_eq!(ranges, vec![1..3, 4..11, 10000..10000001]);
+    assert_eq!(ranges, vec![1..=2, 4..=10, 10000..=10000000, u64::MAX - 5..=u64::MAX]);
     assert_eq!(ids, vec![1, 70, 981]);
 }
 
@@ -106,7 +108,7 @@ fn test_ranges() {
     println!("{last:?}");
     for range in set.data[1..].iter() {
         println!("{range:?}");
-        assert!(range.start > last.end);
+        assert!(*range.start() > *last.end() + 1);
         last = range
     }
 }
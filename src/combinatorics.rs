@@ -1,85 +1,850 @@
 pub mod combinatorics {
     use std::cmp::{max, min};
+    use std::collections::BTreeMap;
+    use crate::algebra::algebra::Integer;
+    use crate::util::search::{exponential_search, lower_bound_by_key, upper_bound_by_key};
     use std::fmt::Debug;
-    use std::iter::Sum;
-    use std::ops::{Add, Range, Sub};
+    use std::ops::{Add, BitAnd, BitOr, BitXor, Bound, RangeBounds, RangeInclusive, Sub};
 
-    pub struct RangeSet<Idx> where Idx: Ord + Copy {
-        pub data: Vec<Range<Idx>>
+    /// Integer-like index types with a smallest and a largest value, so that every range,
+    /// including ones that touch either end of the domain, can be stored inclusively.
+    pub trait Discrete: Ord + Copy {
+        const MIN: Self;
+        const MAX: Self;
+
+        /// the next value, `None` at `MAX`
+        fn succ(self) -> Option<Self>;
+
+        /// the previous value, `None` at `MIN`
+        fn pred(self) -> Option<Self>;
+
+        /// signed type for moving values around, so unsigned indices can move down too
+        type Offset: Copy + Debug + Eq;
+
+        /// `self` moved by `offset`, `None` if that leaves the domain
+        fn checked_offset(self, offset: Self::Offset) -> Option<Self>;
+    }
+
+    macro_rules! discrete {
+        ($($ty:ty => $offset:ty, $shift:ident);*) => {$(
+            impl Discrete for $ty {
+                const MIN: Self = <$ty>::MIN;
+                const MAX: Self = <$ty>::MAX;
+
+                fn succ(self) -> Option<Self> {
+                    self.checked_add(1)
+                }
+
+                fn pred(self) -> Option<Self> {
+                    self.checked_sub(1)
+                }
+
+                type Offset = $offset;
+
+                fn checked_offset(self, offset: $offset) -> Option<Self> {
+                    self.$shift(offset)
+                }
+            }
+        )*};
+    }
+
+    discrete!(
+        u8 => i8, checked_add_signed; u16 => i16, checked_add_signed; u32 => i32, checked_add_signed;
+        u64 => i64, checked_add_signed; u128 => i128, checked_add_signed; usize => isize, checked_add_signed;
+        i8 => i8, checked_add; i16 => i16, checked_add; i32 => i32, checked_add;
+        i64 => i64, checked_add; i128 => i128, checked_add; isize => isize, checked_add
+    );
+
+    /// any kind of range as its first and last element, `None` if it is empty
+    fn normalise<Idx: Discrete>(range: impl RangeBounds<Idx>) -> Option<(Idx, Idx)> {
+        let lo = match range.start_bound() {
+            Bound::Included(&lo) => lo,
+            Bound::Excluded(&lo) => lo.succ()?,
+            Bound::Unbounded => Idx::MIN,
+        };
+        let hi = match range.end_bound() {
+            Bound::Included(&hi) => hi,
+            Bound::Excluded(&hi) => hi.pred()?,
+            Bound::Unbounded => Idx::MAX,
+        };
+
+        if lo <= hi { Some((lo, hi)) } else { None }
+    }
+
+    /// whether a range starting at `lo` overlaps or directly follows one ending at `hi`
+    fn touches<Idx: Discrete>(hi: Idx, lo: Idx) -> bool {
+        hi.succ().is_none_or(|next| lo <= next)
+    }
+
+    /// number of values in `range`, `None` if that doesn't fit into `Idx`
+    fn range_len<Idx: Discrete + Integer>(range: &RangeInclusive<Idx>) -> Option<Idx> {
+        range.end().checked_sub(*range.start())?.succ()
     }
 
-    impl <Idx: Ord + Copy + Debug> RangeSet<Idx> {
+    /// Sorted, disjoint and non-adjacent inclusive ranges. Anything implementing `RangeBounds`
+    /// can be added, so `a..b`, `a..=b`, `a..` and `..b` all work right up to `Idx::MAX`.
+    #[derive(Clone, Debug, PartialEq, Eq)]
+    pub struct RangeSet<Idx> where Idx: Discrete {
+        pub data: Vec<RangeInclusive<Idx>>
+    }
+
+    impl <Idx: Discrete + Debug> RangeSet<Idx> {
         pub fn new() -> RangeSet<Idx> {
             RangeSet { data: Vec::new() }
         }
 
-        pub fn from_vec(vec: Vec<Range<Idx>>) -> RangeSet<Idx> {
-            let mut set = RangeSet::new();
-            for range in vec.into_iter() {
-                set.add(range);
+        /// sorts the ranges by start and merges them in a single pass
+        pub fn from_vec<R: RangeBounds<Idx>>(vec: Vec<R>) -> RangeSet<Idx> {
+            let mut bounds: Vec<(Idx, Idx)> = vec.into_iter().filter_map(normalise).collect();
+            bounds.sort_unstable_by_key(|&(lo, _)| lo);
+
+            let mut data = Vec::with_capacity(bounds.len());
+            for (lo, hi) in bounds {
+                push_merged(&mut data, lo, hi);
             }
 
-            set
+            RangeSet { data }
         }
 
         pub fn contains(&self, val: Idx) -> bool {
-            if self.data.is_empty() { return false; }
+            // the last range starting at or before `val` is the only candidate
+            let idx = upper_bound_by_key(&self.data, &val, |r| *r.start());
+            idx > 0 && *self.data[idx - 1].end() >= val
+        }
+
+        /// `contains` for each of the `queries`, in one forward pass over both lists
+        ///
+        /// `queries` must be sorted ascending (duplicates are fine), otherwise later ones can be
+        /// reported missing; this is only checked in debug builds.
+        pub fn contains_sorted_batch(&self, queries: &[Idx]) -> Vec<bool> {
+            debug_assert!(queries.windows(2).all(|w| w[0] <= w[1]), "queries must be sorted");
+            let mut result = Vec::with_capacity(queries.len());
+            let mut idx = 0;
+            for &val in queries {
+                // gallops, so sparse queries over many ranges skip ahead in O(log gap)
+                idx += exponential_search(&self.data[idx..], |r| *r.end() < val);
+                result.push(idx < self.data.len() && *self.data[idx].start() <= val);
+            }
+
+            result
+        }
+
+        pub fn add<R: RangeBounds<Idx>>(&mut self, range: R) {
+            let Some((mut lo, mut hi)) = normalise(range) else { return };
+
+            let mut first = self.find(lo);
+            if first > 0 && touches(*self.data[first - 1].end(), lo) {
+                first -= 1;
+            }
+            let mut last = first;
+            while last < self.data.len() && touches(hi, *self.data[last].start()) {
+                last += 1;
+            }
+            if first < last {
+                lo = min(lo, *self.data[first].start());
+                hi = max(hi, *self.data[last - 1].end());
+            }
+
+            self.data.splice(first..last, [lo..=hi]);
+        }
+
+        /// adds `lo..=hi`, the way puzzle inputs write ranges as `lo-hi`
+        pub fn add_inclusive(&mut self, lo: Idx, hi: Idx) {
+            self.add(lo..=hi);
+        }
+
+        /// index of the first range starting at or after `start`
+        pub fn find(&self, start: Idx) -> usize {
+            lower_bound_by_key(&self.data, &start, |r| *r.start())
+        }
+
+        /// takes `range` out of the set, splitting the ranges it cuts through
+        pub fn remove<R: RangeBounds<Idx>>(&mut self, range: R) {
+            let Some((lo, hi)) = normalise(range) else { return };
+            cut_out(&mut self.data, lo, hi, |r| r, |r, _| r);
+        }
+
+        /// the parts below `point` and the parts from `point` on
+        pub fn split_at(&self, point: Idx) -> (RangeSet<Idx>, RangeSet<Idx>) {
+            let mut below = Vec::new();
+            let mut above = Vec::new();
+            for range in &self.data {
+                let (lo, hi) = (*range.start(), *range.end());
+                if let Some(before) = point.pred() {
+                    push_merged(&mut below, lo, min(hi, before));
+                }
+                push_merged(&mut above, max(lo, point), hi);
+            }
+
+            (RangeSet { data: below }, RangeSet { data: above })
+        }
+
+        /// keeps only the ranges `pred` holds for
+        pub fn retain(&mut self, pred: impl FnMut(&RangeInclusive<Idx>) -> bool) {
+            self.data.retain(pred);
+        }
+
+        pub fn pop_first(&mut self) -> Option<RangeInclusive<Idx>> {
+            if self.data.is_empty() { None } else { Some(self.data.remove(0)) }
+        }
+
+        pub fn pop_last(&mut self) -> Option<RangeInclusive<Idx>> {
+            self.data.pop()
+        }
+
+        pub fn iter(&self) -> impl Iterator<Item = &RangeInclusive<Idx>> {
+            self.data.iter()
+        }
+
+        /// every contained value in ascending order
+        pub fn points(&self) -> impl Iterator<Item = Idx> + '_ {
+            self.data.iter().flat_map(|range| {
+                let hi = *range.end();
+                std::iter::successors(Some(*range.start()), move |&v| if v < hi { v.succ() } else { None })
+            })
+        }
+
+        /// the holes between consecutive ranges, never empty as touching ranges are merged
+        pub fn gaps(&self) -> impl Iterator<Item = RangeInclusive<Idx>> + '_ {
+            self.data.windows(2).map(|pair| pair[0].end().succ().unwrap()..=pair[1].start().pred().unwrap())
+        }
+
+        /// everything in either set
+        pub fn union(&self, other: &RangeSet<Idx>) -> RangeSet<Idx> {
+            let mut data = Vec::with_capacity(self.data.len() + other.data.len());
+            let (mut i, mut j) = (0, 0);
+            while i < self.data.len() || j < other.data.len() {
+                let next = if j >= other.data.len()
+                    || (i < self.data.len() && self.data[i].start() <= other.data[j].start())
+                {
+                    i += 1;
+                    &self.data[i - 1]
+                } else {
+                    j += 1;
+                    &other.data[j - 1]
+                };
+                push_merged(&mut data, *next.start(), *next.end());
+            }
+
+            RangeSet { data }
+        }
+
+        /// everything in both sets
+        pub fn intersection(&self, other: &RangeSet<Idx>) -> RangeSet<Idx> {
+            let mut data = Vec::new();
+            let (mut i, mut j) = (0, 0);
+            while i < self.data.len() && j < other.data.len() {
+                let (a, b) = (&self.data[i], &other.data[j]);
+                push_merged(&mut data, max(*a.start(), *b.start()), min(*a.end(), *b.end()));
+                if a.end() <= b.end() { i += 1 } else { j += 1 }
+            }
+
+            RangeSet { data }
+        }
+
+        /// everything in `self` but not in `other`
+        pub fn difference(&self, other: &RangeSet<Idx>) -> RangeSet<Idx> {
+            let mut data = Vec::new();
+            let mut j = 0;
+            for range in &self.data {
+                let mut start = *range.start();
+                while j < other.data.len() && *other.data[j].end() < start {
+                    j += 1;
+                }
+                let mut k = j;
+                let mut exhausted = false;
+                while k < other.data.len() && other.data[k].start() <= range.end() {
+                    if let Some(before) = other.data[k].start().pred() {
+                        push_merged(&mut data, start, before);
+                    }
+                    match other.data[k].end().succ() {
+                        Some(after) => start = max(start, after),
+                        None => exhausted = true,
+                    }
+                    k += 1;
+                }
+                if !exhausted {
+                    push_merged(&mut data, start, *range.end());
+                }
+                // the last overlapping range of `other` may reach into the next one
+                j = max(j, k.saturating_sub(1));
+            }
+
+            RangeSet { data }
+        }
+
+        /// everything in exactly one of the sets
+        pub fn symmetric_difference(&self, other: &RangeSet<Idx>) -> RangeSet<Idx> {
+            self.difference(other).union(&other.difference(self))
+        }
+
+        /// everything in `bounds` that isn't in the set
+        pub fn complement_within<R: RangeBounds<Idx>>(&self, bounds: R) -> RangeSet<Idx> {
+            RangeSet::from_vec(vec![bounds]).difference(self)
+        }
+
+        /// every value moved by `offset`, `None` if any of them leaves the domain
+        pub fn shift(&self, offset: Idx::Offset) -> Option<RangeSet<Idx>> {
+            let data = self.data.iter()
+                .map(|r| Some(r.start().checked_offset(offset)?..=r.end().checked_offset(offset)?))
+                .collect::<Option<_>>()?;
+
+            Some(RangeSet { data })
+        }
+
+        /// only the parts inside `bounds`
+        pub fn clamp<R: RangeBounds<Idx>>(&self, bounds: R) -> RangeSet<Idx> {
+            self.intersection(&RangeSet::from_vec(vec![bounds]))
+        }
+    }
 
-            let idx = self.find(&(val..val));
-            if idx >= self.data.len() {
-                self.data[idx-1].contains(&val)
-            } else if idx > 0 {
-                self.data[idx].contains(&val) || self.data[idx-1].contains(&val)
+    impl<Idx: Discrete + Debug + Integer> RangeSet<Idx> {
+        /// The image of the set under `x ↦ a·x + b`.
+        ///
+        /// For `a` in `{-1, 0, 1}` ranges map onto ranges. Any other factor spreads the points
+        /// apart, so the image is listed point by point and fails with `TooManyPoints` past
+        /// `MAP_AFFINE_POINT_LIMIT` of them; use `shift` for plain offsets.
+        pub fn map_affine(&self, a: Idx, b: Idx) -> Result<RangeSet<Idx>, AffineError> {
+            let image = |x: Idx| a.checked_mul(x).and_then(|ax| ax.checked_add(b)).ok_or(AffineError::Overflow);
+            let unit = a == Idx::zero() || a == Idx::one() || a.checked_add(Idx::one()) == Some(Idx::zero());
+
+            let mut bounds = Vec::with_capacity(self.data.len());
+            if unit {
+                for range in &self.data {
+                    let (lo, hi) = (image(*range.start())?, image(*range.end())?);
+                    bounds.push(if lo <= hi { (lo, hi) } else { (hi, lo) });
+                }
             } else {
-                self.data[idx].contains(&val)
+                for x in self.points() {
+                    if bounds.len() == MAP_AFFINE_POINT_LIMIT {
+                        return Err(AffineError::TooManyPoints);
+                    }
+                    let y = image(x)?;
+                    bounds.push((y, y));
+                }
+            }
+            // a decreasing map reverses the order
+            if a < Idx::zero() {
+                bounds.reverse();
+            }
+
+            let mut data = Vec::with_capacity(bounds.len());
+            for (lo, hi) in bounds {
+                push_merged(&mut data, lo, hi);
             }
+            Ok(RangeSet { data })
         }
+    }
 
-        pub fn add(&mut self, range: Range<Idx>) {
-            if self.data.is_empty() {
-                self.data.push(range);
-                return;
+    /// Most points `RangeSet::map_affine` lists one by one before giving up
+    pub const MAP_AFFINE_POINT_LIMIT: usize = 1 << 20;
+
+    #[derive(Clone, Copy, PartialEq, Eq, Debug)]
+    pub enum AffineError {
+        /// some image doesn't fit into the index type
+        Overflow,
+        /// the image has more than `MAP_AFFINE_POINT_LIMIT` separate points
+        TooManyPoints,
+    }
+
+    /// Prefix lengths over a `RangeSet` for O(log n) `rank` and `select`.
+    /// Borrows the set, so it can't go stale while the set is changed.
+    pub struct RangeSetIndex<'a, Idx> where Idx: Discrete {
+        set: &'a RangeSet<Idx>,
+        /// `prefix[i]` is the number of values in the ranges before `i`
+        prefix: Vec<Idx>,
+    }
+
+    impl<Idx: Discrete + Debug + Integer> RangeSet<Idx> {
+        /// Precomputes the prefix lengths, panics if the total count doesn't fit into `Idx`,
+        /// in release builds too
+        pub fn index(&self) -> RangeSetIndex<'_, Idx> {
+            let mut prefix = Vec::with_capacity(self.data.len() + 1);
+            let mut total = Idx::zero();
+            prefix.push(total);
+            for range in &self.data {
+                total = range_len(range)
+                    .and_then(|len| total.checked_add(len))
+                    .expect("RangeSet length overflow");
+                prefix.push(total);
             }
 
-            let mut idx = self.find(&range);
-            self.data.insert(idx, range);
-            while idx < self.data.len() - 1 && self.data[idx].end >= self.data[idx + 1].start {
-                self.data[idx].start = min(self.data[idx + 1].start, self.data[idx].start);
-                self.data[idx].end = max(self.data[idx + 1].end, self.data[idx].end);
-                self.data.remove(idx + 1);
+            RangeSetIndex { set: self, prefix }
+        }
+    }
+
+    /// Once the prefix lengths fit, `rank` stays below the total and `select` lands inside a range,
+    /// so neither can overflow.
+    impl<Idx: Discrete + Debug + Integer> RangeSetIndex<'_, Idx> {
+        /// how many contained values are below `x`
+        pub fn rank(&self, x: Idx) -> Idx {
+            let idx = self.set.find(x);
+            if idx == 0 {
+                return Idx::zero();
             }
-            while idx > 0 && self.data[idx].start <= self.data[idx - 1].end {
-                self.data[idx].start = min(self.data[idx - 1].start, self.data[idx].start);
-                self.data[idx].end = max(self.data[idx - 1].end, self.data[idx].end);
-                self.data.remove(idx - 1);
-                idx -= 1;
+
+            let prev = &self.set.data[idx - 1];
+            if x > *prev.end() {
+                self.prefix[idx]
+            } else {
+                self.prefix[idx - 1] + (x - *prev.start())
             }
         }
 
-        pub fn find(&self, range: &Range<Idx>) -> usize {
-            if self.data.is_empty() { return 0 }
+        /// the `k`th contained value counting from 0, `None` if there are only `k` or fewer
+        pub fn select(&self, k: Idx) -> Option<Idx> {
+            if k < Idx::zero() || k >= *self.prefix.last().unwrap() {
+                return None;
+            }
+
+            // the last range with at most `k` values before it
+            let idx = upper_bound_by_key(&self.prefix, &k, |&before| before) - 1;
+            Some(*self.set.data[idx].start() + (k - self.prefix[idx]))
+        }
+    }
 
-            let mut low = 0;
-            let mut high = self.data.len() - 1;
-            while low < high {
-                let halfway = (low + high) / 2;
-                if self.data[halfway].start == range.start {
-                    return halfway;
+    /// adds all the ranges at once, sorting them and merging with the existing ones
+    impl<Idx: Discrete + Debug, R: RangeBounds<Idx>> Extend<R> for RangeSet<Idx> {
+        fn extend<I: IntoIterator<Item = R>>(&mut self, iter: I) {
+            let added = RangeSet::from_vec(iter.into_iter().collect());
+            *self = self.union(&added);
+        }
+    }
+
+    /// appends `lo..=hi` after the existing ranges, merging if they touch and skipping it if empty
+    fn push_merged<Idx: Discrete>(data: &mut Vec<RangeInclusive<Idx>>, lo: Idx, hi: Idx) {
+        if lo > hi {
+            return;
+        }
+        match data.last_mut() {
+            Some(last) if touches(*last.end(), lo) => *last = *last.start()..=max(*last.end(), hi),
+            _ => data.push(lo..=hi),
+        }
+    }
+
+    /// Indices `first..last` of the entries overlapping `lo..=hi`, for sorted disjoint entries
+    /// whose range is picked out by `range`. Shared by `RangeSet` and `RangeMap`.
+    fn overlap_span<T, Idx: Discrete>(
+        entries: &[T],
+        lo: Idx,
+        hi: Idx,
+        range: impl Fn(&T) -> &RangeInclusive<Idx>,
+    ) -> (usize, usize) {
+        let mut first = lower_bound_by_key(entries, &lo, |e| *range(e).start());
+        if first > 0 && *range(&entries[first - 1]).end() >= lo {
+            first -= 1;
+        }
+        let last = first + entries[first..].iter().take_while(|e| *range(e).start() <= hi).count();
+
+        (first, last)
+    }
+
+    /// Takes `lo..=hi` out of sorted disjoint entries, splitting the ones it cuts through.
+    /// `piece` turns a leftover part of an entry's range back into an entry.
+    fn cut_out<T, Idx: Discrete>(
+        entries: &mut Vec<T>,
+        lo: Idx,
+        hi: Idx,
+        range: impl Fn(&T) -> &RangeInclusive<Idx>,
+        piece: impl Fn(RangeInclusive<Idx>, &T) -> T,
+    ) {
+        let (first, last) = overlap_span(entries, lo, hi, &range);
+        if first == last {
+            return;
+        }
+
+        // both neighbours exist whenever something is left over on that side
+        let first_start = *range(&entries[first]).start();
+        let last_end = *range(&entries[last - 1]).end();
+        let left = (first_start < lo).then(|| piece(first_start..=lo.pred().unwrap(), &entries[first]));
+        let right = (last_end > hi).then(|| piece(hi.succ().unwrap()..=last_end, &entries[last - 1]));
+        entries.splice(first..last, left.into_iter().chain(right));
+    }
+
+    macro_rules! set_operator {
+        ($tr:ident, $method:ident, $op:ident) => {
+            impl<Idx: Discrete + Debug> $tr for &RangeSet<Idx> {
+                type Output = RangeSet<Idx>;
+
+                fn $method(self, rhs: Self) -> RangeSet<Idx> {
+                    self.$op(rhs)
                 }
-                if self.data[halfway].start < range.start {
-                    low = halfway + 1;
+            }
+
+            impl<Idx: Discrete + Debug> $tr for RangeSet<Idx> {
+                type Output = RangeSet<Idx>;
+
+                fn $method(self, rhs: Self) -> RangeSet<Idx> {
+                    self.$op(&rhs)
+                }
+            }
+        };
+    }
+
+    set_operator!(BitOr, bitor, union);
+    set_operator!(BitAnd, bitand, intersection);
+    set_operator!(Sub, sub, difference);
+    set_operator!(BitXor, bitxor, symmetric_difference);
+
+    impl<Idx: Discrete + Integer> RangeSet<Idx> {
+        /// number of elements, panics if that doesn't fit into `Idx`, in release builds too
+        pub fn len(&self) -> Idx {
+            self.data.iter()
+                .try_fold(Idx::zero(), |total, range| total.checked_add(range_len(range)?))
+                .expect("RangeSet length overflow")
+        }
+    }
+
+    /// Values attached to disjoint inclusive ranges of keys, kept sorted like `RangeSet`.
+    /// Neighbouring ranges with equal values are merged into one.
+    #[derive(Clone, Debug, PartialEq, Eq)]
+    pub struct RangeMap<Idx, V> where Idx: Discrete {
+        pub data: Vec<(RangeInclusive<Idx>, V)>
+    }
+
+    impl<Idx: Discrete, V> Default for RangeMap<Idx, V> {
+        fn default() -> Self {
+            RangeMap { data: Vec::new() }
+        }
+    }
+
+    impl<Idx: Discrete + Debug, V: Clone + PartialEq> RangeMap<Idx, V> {
+        pub fn new() -> RangeMap<Idx, V> {
+            RangeMap { data: Vec::new() }
+        }
+
+        /// index of the first entry starting at or after `start`
+        pub fn find(&self, start: Idx) -> usize {
+            lower_bound_by_key(&self.data, &start, |(r, _)| *r.start())
+        }
+
+        pub fn get(&self, key: Idx) -> Option<&V> {
+            let idx = upper_bound_by_key(&self.data, &key, |(r, _)| *r.start());
+            if idx > 0 && *self.data[idx - 1].0.end() >= key {
+                Some(&self.data[idx - 1].1)
+            } else {
+                None
+            }
+        }
+
+        /// the covered parts of `range`, each clipped to it, in order
+        pub fn query<R: RangeBounds<Idx>>(&self, range: R) -> impl Iterator<Item = (RangeInclusive<Idx>, &V)> {
+            let (lo, hi, first, last) = match normalise(range) {
+                Some((lo, hi)) => {
+                    let (first, last) = overlap_span(&self.data, lo, hi, |(r, _)| r);
+                    (lo, hi, first, last)
+                }
+                None => (Idx::MIN, Idx::MIN, 0, 0),
+            };
+
+            self.data[first..last]
+                .iter()
+                .map(move |(r, v)| (max(*r.start(), lo)..=min(*r.end(), hi), v))
+        }
+
+        /// unmaps `range`, splitting the entries it cuts through
+        pub fn remove<R: RangeBounds<Idx>>(&mut self, range: R) {
+            let Some((lo, hi)) = normalise(range) else { return };
+            cut_out(&mut self.data, lo, hi, |(r, _)| r, |r, (_, v)| (r, v.clone()));
+        }
+
+        /// maps all of `range` to `value`, overwriting whatever was there
+        pub fn insert<R: RangeBounds<Idx>>(&mut self, range: R, value: V) {
+            let Some((lo, hi)) = normalise(range) else { return };
+            self.remove(lo..=hi);
+
+            let idx = self.find(lo);
+            self.data.insert(idx, (lo..=hi, value));
+            self.coalesce_at(idx + 1);
+            self.coalesce_at(idx);
+        }
+
+        /// Maps the uncovered parts of `range` to `value` and the covered ones to
+        /// `merge(old, &value)`, e.g. for adding offsets where mapping rules overlap.
+        pub fn insert_with<R: RangeBounds<Idx>>(&mut self, range: R, value: V, mut merge: impl FnMut(&V, &V) -> V) {
+            let Some((lo, hi)) = normalise(range) else { return };
+
+            let mut pieces = Vec::new();
+            let mut cursor = Some(lo);
+            for (r, old) in self.query(lo..=hi) {
+                if let Some(from) = cursor && from < *r.start() {
+                    pieces.push((from..=r.start().pred().unwrap(), value.clone()));
+                }
+                cursor = r.end().succ();
+                pieces.push((r, merge(old, &value)));
+            }
+            if let Some(from) = cursor && from <= hi {
+                pieces.push((from..=hi, value));
+            }
+
+            for (r, v) in pieces {
+                self.insert(r, v);
+            }
+        }
+
+        /// merges the entry at `idx` into its predecessor if they touch and agree
+        fn coalesce_at(&mut self, idx: usize) {
+            if idx == 0 || idx >= self.data.len() {
+                return;
+            }
+            let (prev, next) = (&self.data[idx - 1], &self.data[idx]);
+            if prev.0.end().succ() == Some(*next.0.start()) && prev.1 == next.1 {
+                let end = *next.0.end();
+                self.data[idx - 1].0 = *prev.0.start()..=end;
+                self.data.remove(idx);
+            }
+        }
+    }
+
+    impl<Idx: Discrete + Debug> RangeMap<Idx, Idx::Offset> {
+        /// Sends a whole set through the map read as an offset table: the parts covered by an entry
+        /// move by its value, the rest stay where they are. Chaining calls runs a multi-stage
+        /// mapping without expanding the set point by point. `None` if anything overflows.
+        pub fn shift_set(&self, set: &RangeSet<Idx>) -> Option<RangeSet<Idx>> {
+            let mut moved = Vec::new();
+            for range in &set.data {
+                for (part, &offset) in self.query(range.clone()) {
+                    moved.push(part.start().checked_offset(offset)?..=part.end().checked_offset(offset)?);
+                }
+            }
+            let covered = RangeSet::from_vec(self.data.iter().map(|(range, _)| range.clone()).collect());
+
+            Some(RangeSet::from_vec(moved).union(&set.difference(&covered)))
+        }
+    }
+
+    /// Multiset of ranges: unlike `RangeSet` it remembers how many of the added ranges
+    /// cover each segment, as a `RangeMap` from segment to depth.
+    #[derive(Clone, Debug, PartialEq, Eq)]
+    pub struct RangeCounter<Idx> where Idx: Discrete {
+        pub depths: RangeMap<Idx, usize>
+    }
+
+    impl<Idx: Discrete + Debug> RangeCounter<Idx> {
+        pub fn new() -> RangeCounter<Idx> {
+            RangeCounter { depths: RangeMap::new() }
+        }
+
+        /// Sweeps over the sorted start and end points once instead of adding range by range
+        pub fn from_vec<R: RangeBounds<Idx>>(vec: Vec<R>) -> RangeCounter<Idx> {
+            // (position, change in depth), ranges ending at `Idx::MAX` never drop out
+            let mut events: Vec<(Idx, isize)> = Vec::with_capacity(2 * vec.len());
+            for (lo, hi) in vec.into_iter().filter_map(normalise) {
+                events.push((lo, 1));
+                if let Some(after) = hi.succ() {
+                    events.push((after, -1));
+                }
+            }
+            events.sort_unstable();
+
+            let mut data = Vec::new();
+            // start of the current segment, ranges ending and starting at the same point cancel out
+            let mut segment_start = Idx::MIN;
+            let mut depth = 0;
+            let mut i = 0;
+            while i < events.len() {
+                let at = events[i].0;
+                let before = depth;
+                while i < events.len() && events[i].0 == at {
+                    depth += events[i].1;
+                    i += 1;
+                }
+                if depth == before {
+                    continue;
+                }
+
+                if before > 0 {
+                    data.push((segment_start..=at.pred().unwrap(), before as usize));
+                }
+                segment_start = at;
+            }
+            if depth > 0 {
+                data.push((segment_start..=Idx::MAX, depth as usize));
+            }
+
+            RangeCounter { depths: RangeMap { data } }
+        }
+
+        pub fn add<R: RangeBounds<Idx>>(&mut self, range: R) {
+            self.depths.insert_with(range, 1, |old, _| old + 1);
+        }
+
+        /// how many of the added ranges contain `point`
+        pub fn depth(&self, point: Idx) -> usize {
+            self.depths.get(point).copied().unwrap_or(0)
+        }
+
+        /// all points covered by at least `k` of the ranges
+        pub fn covered_at_least(&self, k: usize) -> RangeSet<Idx> {
+            if k == 0 {
+                return RangeSet::from_vec(vec![..]);
+            }
+
+            let mut data = Vec::new();
+            for (range, _) in self.depths.data.iter().filter(|(_, depth)| *depth >= k) {
+                push_merged(&mut data, *range.start(), *range.end());
+            }
+            RangeSet { data }
+        }
+
+        /// the highest depth and everywhere it is reached, `None` if nothing was added
+        pub fn max_depth(&self) -> Option<(usize, RangeSet<Idx>)> {
+            let max = self.depths.data.iter().map(|(_, depth)| *depth).max()?;
+            Some((max, self.covered_at_least(max)))
+        }
+    }
+
+    impl<Idx: Discrete + Add<Output=Idx> + Sub<Output=Idx>> RangeCounter<Idx> {
+        /// Total length covered by exactly `d` ranges, for every `d` that occurs.
+        /// Panics if a single length doesn't fit into `Idx`.
+        pub fn length_by_depth(&self) -> BTreeMap<usize, Idx> {
+            let mut result = BTreeMap::new();
+            for (range, depth) in &self.depths.data {
+                let len = (*range.end() - *range.start()).succ().expect("RangeCounter length overflow");
+                result.entry(*depth).and_modify(|total| *total = *total + len).or_insert(len);
+            }
+
+            result
+        }
+    }
+
+    impl<Idx: Discrete> Default for RangeCounter<Idx> {
+        fn default() -> Self {
+            RangeCounter { depths: RangeMap::default() }
+        }
+    }
+
+    /// Static centred interval tree over labelled ranges, for finding which of the original
+    /// ranges contain a point rather than just whether any does.
+    ///
+    /// Every node keeps the intervals straddling its centre sorted both by start and by end, and
+    /// the centres are median endpoints, so a stabbing query visits O(log n) nodes and stops
+    /// scanning each node's lists at the first miss: O(log n + k) for k matches.
+    #[derive(Clone, Debug)]
+    pub struct IntervalTree<Idx, L> where Idx: Discrete {
+        /// sorted by start, nodes refer to intervals by their position here
+        intervals: Vec<(RangeInclusive<Idx>, L)>,
+        nodes: Vec<CentreNode<Idx>>,
+        root: Option<usize>,
+    }
+
+    #[derive(Clone, Debug)]
+    struct CentreNode<Idx> {
+        centre: Idx,
+        /// intervals containing `centre`, ascending by start
+        by_start: Vec<usize>,
+        /// the same intervals, descending by end
+        by_end: Vec<usize>,
+        /// subtree of the intervals ending before `centre`
+        left: Option<usize>,
+        /// subtree of the intervals starting after `centre`
+        right: Option<usize>,
+    }
+
+    impl<Idx: Discrete + Debug, L> IntervalTree<Idx, L> {
+        /// builds the tree in one go, empty ranges are dropped as they can't match anything
+        pub fn from_vec<R: RangeBounds<Idx>>(vec: Vec<(R, L)>) -> IntervalTree<Idx, L> {
+            let mut intervals: Vec<_> = vec.into_iter()
+                .filter_map(|(range, label)| normalise(range).map(|(lo, hi)| (lo..=hi, label)))
+                .collect();
+            intervals.sort_by_key(|(range, _)| *range.start());
+
+            let mut tree = IntervalTree { intervals, nodes: Vec::new(), root: None };
+            tree.root = tree.build((0..tree.intervals.len()).collect());
+
+            tree
+        }
+
+        /// builds the subtree over the ascending positions `members` and returns its node
+        fn build(&mut self, members: Vec<usize>) -> Option<usize> {
+            if members.is_empty() {
+                return None;
+            }
+            // the median endpoint belongs to some member, so every node keeps at least one and
+            // each side gets at most half of them
+            let mut endpoints: Vec<Idx> = members.iter()
+                .flat_map(|&i| [*self.intervals[i].0.start(), *self.intervals[i].0.end()])
+                .collect();
+            let mid = endpoints.len() / 2;
+            let centre = *endpoints.select_nth_unstable(mid).1;
+
+            let (mut left, mut right, mut by_start) = (Vec::new(), Vec::new(), Vec::new());
+            for i in members {
+                let range = &self.intervals[i].0;
+                if *range.end() < centre {
+                    left.push(i);
+                } else if *range.start() > centre {
+                    right.push(i);
                 } else {
-                    high = if halfway == 0 { 0 } else { halfway - 1 };
+                    by_start.push(i);
                 }
             }
+            let mut by_end = by_start.clone();
+            by_end.sort_by_key(|&i| std::cmp::Reverse(*self.intervals[i].0.end()));
+
+            let left = self.build(left);
+            let right = self.build(right);
+            self.nodes.push(CentreNode { centre, by_start, by_end, left, right });
+
+            Some(self.nodes.len() - 1)
+        }
+
+        pub fn len(&self) -> usize {
+            self.intervals.len()
+        }
+
+        pub fn is_empty(&self) -> bool {
+            self.intervals.is_empty()
+        }
+
+        /// all intervals containing `point`, in no particular order
+        pub fn containing(&self, point: Idx) -> Vec<&(RangeInclusive<Idx>, L)> {
+            let mut result = Vec::new();
+            self.collect_containing(point, &mut result);
+
+            result
+        }
+
+        /// all intervals sharing at least one point with `range`, in no particular order
+        ///
+        /// These are the ones containing its start plus the ones starting inside it, so this is
+        /// a stabbing query and a binary search, again O(log n + k).
+        pub fn overlapping<R: RangeBounds<Idx>>(&self, range: R) -> Vec<&(RangeInclusive<Idx>, L)> {
+            let mut result = Vec::new();
+            if let Some((lo, hi)) = normalise(range) {
+                self.collect_containing(lo, &mut result);
+                let after = upper_bound_by_key(&self.intervals, &lo, |(range, _)| *range.start());
+                result.extend(self.intervals[after..].iter().take_while(|(range, _)| *range.start() <= hi));
+            }
+
+            result
+        }
 
-            if self.data[low].start < range.start { low + 1 } else { low }
+        fn collect_containing<'a>(&'a self, point: Idx, result: &mut Vec<&'a (RangeInclusive<Idx>, L)>) {
+            let mut current = self.root;
+            while let Some(index) = current {
+                let node = &self.nodes[index];
+                let (order, reaches): (&[usize], &dyn Fn(usize) -> bool) = if point < node.centre {
+                    current = node.left;
+                    (&node.by_start, &|i| *self.intervals[i].0.start() <= point)
+                } else if point > node.centre {
+                    current = node.right;
+                    (&node.by_end, &|i| *self.intervals[i].0.end() >= point)
+                } else {
+                    current = None;
+                    (&node.by_start, &|_| true)
+                };
+                result.extend(order.iter().take_while(|&&i| reaches(i)).map(|&i| &self.intervals[i]));
+            }
         }
     }
 
-    impl<Idx: Ord + Copy + Sum + Sub<Output=Idx>> RangeSet<Idx> {
-        pub fn len(&self) -> Idx {
-            self.data.iter().map(|r| r.end - r.start).sum()
+    /// small linear congruential generator for the randomised tests, yields values below `bound`
+    #[cfg(test)]
+    fn pseudo_random(mut seed: u64) -> impl FnMut(u64) -> u64 {
+        move |bound| {
+            seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+            (seed >> 33) % bound
         }
     }
 
@@ -87,40 +852,194 @@ pub mod combinatorics {
     fn test_add() {
         let mut set = RangeSet { data: vec![] };
         set.add(1..3);
-        assert_eq!(set.data, vec![1..3]);
+        assert_eq!(set.data, vec![1..=2]);
 
         set.add(20..30);
-        assert_eq!(set.data, vec![1..3, 20..30]);
+        assert_eq!(set.data, vec![1..=2, 20..=29]);
         set.add(6..12);
-        assert_eq!(set.data, vec![1..3, 6..12, 20..30]);
+        assert_eq!(set.data, vec![1..=2, 6..=11, 20..=29]);
         set.add(34..40);
-        assert_eq!(set.data, vec![1..3, 6..12, 20..30, 34..40]);
+        assert_eq!(set.data, vec![1..=2, 6..=11, 20..=29, 34..=39]);
 
         set.add(12..13);
-        assert_eq!(set.data, vec![1..3, 6..13, 20..30, 34..40]);
+        assert_eq!(set.data, vec![1..=2, 6..=12, 20..=29, 34..=39]);
 
         set.add(19..21);
-        assert_eq!(set.data, vec![1..3, 6..13, 19..30, 34..40]);
+        assert_eq!(set.data, vec![1..=2, 6..=12, 19..=29, 34..=39]);
 
         set.add(2..46);
-        assert_eq!(set.data, vec![1..46]);
+        assert_eq!(set.data, vec![1..=45]);
+    }
+
+    #[test]
+    fn test_bulk() {
+        let ranges = vec![20..30, 6..12, 1..3, 34..40, 12..13, 19..21, 40..41];
+        let bulk = RangeSet::from_vec(ranges.clone());
+        let mut one_by_one = RangeSet::new();
+        for range in ranges {
+            one_by_one.add(range);
+        }
+        assert_eq!(bulk, one_by_one);
+        assert_eq!(bulk.data, vec![1..=2, 6..=12, 19..=29, 34..=40]);
+
+        let mut extended = RangeSet::from_vec(vec![0..=1]);
+        extended.extend([3..=4, 50..=60, 2..=2]);
+        extended.extend(vec![45..=46]);
+        assert_eq!(extended.data, vec![0..=4, 45..=46, 50..=60]);
+
+        let queries = [0, 1, 2, 3, 5, 6, 12, 13, 25, 40, 41, 100];
+        assert_eq!(
+            bulk.contains_sorted_batch(&queries),
+            queries.iter().map(|&q| bulk.contains(q)).collect::<Vec<_>>()
+        );
+        assert_eq!(RangeSet::new().contains_sorted_batch(&[1, 2]), vec![false, false]);
+    }
+
+    #[test]
+    fn test_iteration() {
+        let set = RangeSet::from_vec(vec![1..=2, 5..=7, 10..=10]);
+        assert_eq!(set.iter().cloned().collect::<Vec<_>>(), set.data);
+        assert_eq!(set.points().collect::<Vec<_>>(), vec![1, 2, 5, 6, 7, 10]);
+        assert_eq!(set.gaps().collect::<Vec<_>>(), vec![3..=4, 8..=9]);
+        assert_eq!(RangeSet::from_vec(vec![250u8..=255]).points().count(), 6);
+        assert_eq!(RangeSet::<u8>::new().gaps().count(), 0);
+    }
+
+    #[test]
+    fn test_rank_select() {
+        let set = RangeSet::from_vec(vec![1..=2, 5..=7, 10..=10]);
+        let index = set.index();
+        let points: Vec<_> = set.points().collect();
+        for x in 0..12 {
+            assert_eq!(index.rank(x), points.iter().filter(|&&p| p < x).count() as i32);
+        }
+        for k in 0..6 {
+            assert_eq!(index.select(k), Some(points[k as usize]));
+        }
+        assert_eq!(index.select(6), None);
+        assert_eq!(index.select(-1), None);
+
+        let ids = RangeSet::from_vec(vec![u64::MAX - 9..=u64::MAX, 0..=999]);
+        let index = ids.index();
+        assert_eq!(index.select(999), Some(999));
+        assert_eq!(index.select(1000), Some(u64::MAX - 9));
+        assert_eq!(index.select(1009), Some(u64::MAX));
+        assert_eq!(index.rank(u64::MAX), 1009);
+        assert_eq!(RangeSet::<u64>::new().index().select(0), None);
+
+        let signed = RangeSet::from_vec(vec![-100i8..=-90, 10..=20]);
+        let signed = signed.index();
+        assert_eq!(signed.rank(15), 16);
+        assert_eq!(signed.select(21), Some(20));
+    }
+
+    #[test]
+    #[should_panic(expected = "RangeSet length overflow")]
+    fn test_index_overflow() {
+        RangeSet::from_vec(vec![-100i8..=0, 10..=40]).index();
+    }
+
+    #[test]
+    #[should_panic(expected = "RangeSet length overflow")]
+    fn test_len_overflow() {
+        RangeSet::from_vec(vec![-100i8..=100]).len();
+    }
+
+    #[test]
+    fn test_bounds() {
+        let mut set = RangeSet::new();
+        set.add_inclusive(u64::MAX - 3, u64::MAX);
+        set.add_inclusive(5, 5);
+        assert_eq!(set.data, vec![5..=5, u64::MAX - 3..=u64::MAX]);
+        assert!(set.contains(u64::MAX));
+        assert_eq!(set.len(), 5);
+        assert_eq!(RangeSet::from_vec(vec![-100i8..=26]).len(), 127);
+
+        set.add(..3);
+        set.add(3..5);
+        assert_eq!(set.data, vec![0..=5, u64::MAX - 3..=u64::MAX]);
+        set.add(100..);
+        assert_eq!(set.data, vec![0..=5, 100..=u64::MAX]);
+        set.remove(u64::MAX..);
+        assert_eq!(set.data, vec![0..=5, 100..=u64::MAX - 1]);
+
+        let mut bytes = RangeSet::new();
+        bytes.add(..);
+        assert_eq!(bytes.data, vec![0u8..=255]);
+        assert_eq!(bytes.complement_within(..).data, vec![]);
+        bytes.remove(1..255);
+        assert_eq!(bytes.data, vec![0..=0, 255..=255]);
+        assert_eq!(bytes.complement_within(..).data, vec![1..=254]);
+
+        let mut signed = RangeSet::new();
+        signed.add(i8::MIN..=-100);
+        signed.add(100..);
+        assert_eq!((&signed - &RangeSet::from_vec(vec![i8::MIN..=i8::MIN, i8::MAX..=i8::MAX])).data,
+                   vec![-127..=-100, 100..=126]);
+
+        set.add(7..7);
+        set.add_inclusive(9, 8);
+        assert_eq!(set.data, vec![0..=5, 100..=u64::MAX - 1]);
+    }
+
+    #[test]
+    fn test_remove() {
+        let mut set = RangeSet::from_vec(vec![1..10, 20..30, 34..40]);
+        set.remove(0..1);
+        assert_eq!(set.data, vec![1..=9, 20..=29, 34..=39]);
+
+        set.remove(3..5);
+        assert_eq!(set.data, vec![1..=2, 5..=9, 20..=29, 34..=39]);
+        set.remove(8..22);
+        assert_eq!(set.data, vec![1..=2, 5..=7, 22..=29, 34..=39]);
+        set.remove(22..23);
+        assert_eq!(set.data, vec![1..=2, 5..=7, 23..=29, 34..=39]);
+        set.remove(39..50);
+        assert_eq!(set.data, vec![1..=2, 5..=7, 23..=29, 34..=38]);
+        set.remove(30..34);
+        assert_eq!(set.data, vec![1..=2, 5..=7, 23..=29, 34..=38]);
+
+        set.remove(2..35);
+        assert_eq!(set.data, vec![1..=1, 35..=38]);
+        set.remove(0..46);
+        assert_eq!(set.data, vec![]);
+    }
+
+    #[test]
+    fn test_split_and_pop() {
+        let mut set = RangeSet::from_vec(vec![1..5, 8..12, 20..25]);
+        let (below, above) = set.split_at(10);
+        assert_eq!(below.data, vec![1..=4, 8..=9]);
+        assert_eq!(above.data, vec![10..=11, 20..=24]);
+        let (below, above) = set.split_at(5);
+        assert_eq!(below.data, vec![1..=4]);
+        assert_eq!(above.data, vec![8..=11, 20..=24]);
+
+        set.retain(|r| r.end() - r.start() >= 4);
+        assert_eq!(set.data, vec![20..=24]);
+
+        let mut set = RangeSet::from_vec(vec![1..5, 8..12, 20..25]);
+        assert_eq!(set.pop_first(), Some(1..=4));
+        assert_eq!(set.pop_last(), Some(20..=24));
+        assert_eq!(set.pop_last(), Some(8..=11));
+        assert_eq!(set.pop_first(), None);
     }
 
     #[test]
     fn test_find() {
-        let set = RangeSet { data: vec![1..3, 3..6, 10..12]};
-        assert_eq!(set.find(&(1..2)), 0);
-        assert_eq!(set.find(&(2..10)), 1);
-        assert_eq!(set.find(&(0..0)), 0);
-        assert_eq!(set.find(&(15..20)), 3);
-        assert_eq!(set.find(&(11..20)), 3);
-        assert_eq!(set.find(&(3..12)), 1);
-        assert_eq!(set.find(&(7..9)), 2);
+        let set = RangeSet { data: vec![1..=2, 3..=5, 10..=11]};
+        assert_eq!(set.find(1), 0);
+        assert_eq!(set.find(2), 1);
+        assert_eq!(set.find(0), 0);
+        assert_eq!(set.find(15), 3);
+        assert_eq!(set.find(11), 3);
+        assert_eq!(set.find(3), 1);
+        assert_eq!(set.find(7), 2);
     }
 
     #[test]
     fn test_contains() {
-        let set = RangeSet { data: vec![1..3, 3..6, 10..12]};
+        let set = RangeSet { data: vec![1..=2, 3..=5, 10..=11]};
         assert!(!set.contains(0));
         assert!( set.contains(1));
         assert!( set.contains(2));
@@ -132,4 +1051,257 @@ pub mod combinatorics {
         assert!( set.contains(11));
         assert!(!set.contains(12));
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_set_algebra() {
+        let a = RangeSet::from_vec(vec![1..5, 8..12, 20..25]);
+        let b = RangeSet::from_vec(vec![3..9, 11..21, 30..31]);
+
+        assert_eq!((&a | &b).data, vec![1..=24, 30..=30]);
+        assert_eq!((&a & &b).data, vec![3..=4, 8..=8, 11..=11, 20..=20]);
+        assert_eq!((&a - &b).data, vec![1..=2, 9..=10, 21..=24]);
+        assert_eq!((&b - &a).data, vec![5..=7, 12..=19, 30..=30]);
+        assert_eq!((&a ^ &b).data, vec![1..=2, 5..=7, 9..=10, 12..=19, 21..=24, 30..=30]);
+
+        let wide = RangeSet::new().complement_within(0..100);
+        assert_eq!((&wide - &a).data, vec![0..=0, 5..=7, 12..=19, 25..=99]);
+        assert_eq!((&a - &wide).data, vec![]);
+        assert_eq!(a.clone() & RangeSet::new(), RangeSet::new());
+        assert_eq!(a.clone() | RangeSet::new(), a);
+
+        assert_eq!(a.complement_within(0..22).data, vec![0..=0, 5..=7, 12..=19]);
+        assert_eq!(a.complement_within(2..4).data, vec![]);
+        assert_eq!(RangeSet::new().complement_within(2..4).data, vec![2..=3]);
+    }
+
+    #[test]
+    fn test_range_map() {
+        let mut map = RangeMap::new();
+        map.insert(10..20, 'a');
+        map.insert(30..=39, 'b');
+        assert_eq!(map.data, vec![(10..=19, 'a'), (30..=39, 'b')]);
+        assert_eq!(map.get(9), None);
+        assert_eq!(map.get(10), Some(&'a'));
+        assert_eq!(map.get(19), Some(&'a'));
+        assert_eq!(map.get(25), None);
+        assert_eq!(map.get(39), Some(&'b'));
+
+        map.insert(15..35, 'c');
+        assert_eq!(map.data, vec![(10..=14, 'a'), (15..=34, 'c'), (35..=39, 'b')]);
+        map.insert(12..15, 'c');
+        assert_eq!(map.data, vec![(10..=11, 'a'), (12..=34, 'c'), (35..=39, 'b')]);
+        map.insert(35..=35, 'c');
+        assert_eq!(map.data, vec![(10..=11, 'a'), (12..=35, 'c'), (36..=39, 'b')]);
+
+        let covered: Vec<_> = map.query(0..=12).collect();
+        assert_eq!(covered, vec![(10..=11, &'a'), (12..=12, &'c')]);
+        let covered: Vec<_> = map.query(37..).collect();
+        assert_eq!(covered, vec![(37..=39, &'b')]);
+        assert_eq!(map.query(40..50).count(), 0);
+
+        map.remove(11..=36);
+        assert_eq!(map.data, vec![(10..=10, 'a'), (37..=39, 'b')]);
+    }
+
+    #[test]
+    fn test_range_map_merge() {
+        let mut offsets: RangeMap<u64, i64> = RangeMap::new();
+        offsets.insert_with(0..10, 5, |old, new| old + new);
+        offsets.insert_with(5..15, 5, |old, new| old + new);
+        assert_eq!(offsets.data, vec![(0..=4, 5), (5..=9, 10), (10..=14, 5)]);
+
+        offsets.insert_with(3..=u64::MAX, -5, |old, new| old + new);
+        assert_eq!(offsets.data, vec![(0..=2, 5), (3..=4, 0), (5..=9, 5), (10..=14, 0), (15..=u64::MAX, -5)]);
+
+        offsets.insert_with(.., 0, |old, _| old - 5);
+        assert_eq!(offsets.data, vec![(0..=2, 0), (3..=4, -5), (5..=9, 0), (10..=14, -5), (15..=u64::MAX, -10)]);
+        offsets.insert(.., 1);
+        assert_eq!(offsets.data, vec![(0..=u64::MAX, 1)]);
+    }
+
+    #[test]
+    fn test_range_counter() {
+        let ranges = vec![3..=5, 10..=14, 16..=20, 12..=18, 13..=13];
+        let counter = RangeCounter::from_vec(ranges.clone());
+        let mut incremental = RangeCounter::new();
+        for range in ranges {
+            incremental.add(range);
+        }
+        assert_eq!(counter, incremental);
+        assert_eq!(counter.depths.data, vec![
+            (3..=5, 1), (10..=11, 1), (12..=12, 2), (13..=13, 3), (14..=14, 2), (15..=15, 1), (16..=18, 2), (19..=20, 1),
+        ]);
+
+        assert_eq!(counter.depth(2), 0);
+        assert_eq!(counter.depth(13), 3);
+        assert_eq!(counter.depth(17), 2);
+        assert_eq!(counter.covered_at_least(1), RangeSet::from_vec(vec![3..=5, 10..=20]));
+        assert_eq!(counter.covered_at_least(2).data, vec![12..=14, 16..=18]);
+        assert_eq!(counter.max_depth(), Some((3, RangeSet::from_vec(vec![13..=13]))));
+        assert_eq!(counter.length_by_depth(), BTreeMap::from([(1, 8), (2, 5), (3, 1)]));
+
+        assert_eq!(RangeCounter::<u8>::new().max_depth(), None);
+    }
+
+    #[test]
+    fn test_range_counter_bounds() {
+        let counter = RangeCounter::from_vec(vec![
+            (Bound::Included(250u8), Bound::Unbounded),
+            (Bound::Unbounded, Bound::Excluded(3)),
+            (Bound::Included(0), Bound::Unbounded),
+        ]);
+        assert_eq!(counter.depths.data, vec![(0..=2, 2), (3..=249, 1), (250..=255, 2)]);
+        assert_eq!(counter.length_by_depth(), BTreeMap::from([(1, 247), (2, 9)]));
+
+        let touching = RangeCounter::from_vec(vec![1..=4, 5..=9, 5..=5]);
+        assert_eq!(touching.depths.data, vec![(1..=4, 1), (5..=5, 2), (6..=9, 1)]);
+        let chained = RangeCounter::from_vec(vec![1..5, 5..10]);
+        assert_eq!(chained.depths.data, vec![(1..=9, 1)]);
+    }
+
+    #[test]
+    fn test_interval_tree() {
+        let tree = IntervalTree::from_vec(vec![
+            (3..=5, "a"),
+            (10..=14, "b"),
+            (16..=20, "c"),
+            (12..=18, "d"),
+        ]);
+        assert_eq!(tree.len(), 4);
+
+        let labels = |found: Vec<&(RangeInclusive<u64>, &'static str)>| -> Vec<&str> {
+            let mut labels: Vec<_> = found.into_iter().map(|(_, label)| *label).collect();
+            labels.sort();
+            labels
+        };
+        assert_eq!(labels(tree.containing(1)), Vec::<&str>::new());
+        assert_eq!(labels(tree.containing(5)), vec!["a"]);
+        assert_eq!(labels(tree.containing(11)), vec!["b"]);
+        assert_eq!(labels(tree.containing(17)), vec!["c", "d"]);
+        assert_eq!(labels(tree.containing(32)), Vec::<&str>::new());
+
+        assert_eq!(labels(tree.overlapping(5..10)), vec!["a"]);
+        assert_eq!(labels(tree.overlapping(6..=10)), vec!["b"]);
+        assert_eq!(labels(tree.overlapping(14..16)), vec!["b", "d"]);
+        assert_eq!(labels(tree.overlapping(..)), vec!["a", "b", "c", "d"]);
+        assert_eq!(labels(tree.overlapping(8..8)), Vec::<&str>::new());
+    }
+
+    #[test]
+    fn test_interval_tree_brute_force() {
+        let mut next = pseudo_random(12345);
+
+        let intervals: Vec<_> = (0..200).map(|i| {
+            let lo = next(1000);
+            (lo..=lo + next(50), i)
+        }).collect();
+        let tree = IntervalTree::from_vec(intervals.clone());
+
+        for _ in 0..200 {
+            let lo = next(1100);
+            let hi = lo + next(20);
+            let mut expected: Vec<_> = intervals.iter()
+                .filter(|(range, _)| *range.start() <= hi && *range.end() >= lo)
+                .map(|(_, label)| *label)
+                .collect();
+            let mut found: Vec<_> = tree.overlapping(lo..=hi).into_iter().map(|(_, label)| *label).collect();
+            expected.sort();
+            found.sort();
+            assert_eq!(found, expected);
+
+            let mut expected: Vec<_> = intervals.iter()
+                .filter(|(range, _)| range.contains(&lo))
+                .map(|(_, label)| *label)
+                .collect();
+            let mut found: Vec<_> = tree.containing(lo).into_iter().map(|(_, label)| *label).collect();
+            expected.sort();
+            found.sort();
+            assert_eq!(found, expected);
+        }
+    }
+
+    #[test]
+    fn test_find_contains_against_linear_scan() {
+        let mut next = pseudo_random(987654321);
+
+        for _ in 0..50 {
+            let count = next(20) as usize;
+            let set = RangeSet::from_vec((0..count).map(|_| {
+                let lo = next(200);
+                lo..=lo + next(10)
+            }).collect());
+
+            for x in 0..220 {
+                let find = set.data.iter().position(|r| *r.start() >= x).unwrap_or(set.data.len());
+                let contains = set.data.iter().any(|r| r.contains(&x));
+                assert_eq!(set.find(x), find);
+                assert_eq!(set.contains(x), contains);
+            }
+        }
+    }
+
+    #[test]
+    fn test_shift_and_affine() {
+        let set = RangeSet::from_vec(vec![1..=2, 5..=7]);
+        assert_eq!(set.shift(10), Some(RangeSet::from_vec(vec![11..=12, 15..=17])));
+        assert_eq!(set.shift(-1), Some(RangeSet::from_vec(vec![0..=1, 4..=6])));
+        assert_eq!(set.shift(i32::MAX - 6), None);
+
+        assert_eq!(set.map_affine(1, -1).ok(), set.shift(-1));
+        assert_eq!(set.map_affine(-1, 0).unwrap().data, vec![-7..=-5, -2..=-1]);
+        assert_eq!(set.map_affine(3, 1).unwrap().data, vec![4..=4, 7..=7, 16..=16, 19..=19, 22..=22]);
+        assert_eq!(set.map_affine(-2, 0).unwrap().data, vec![-14..=-14, -12..=-12, -10..=-10, -4..=-4, -2..=-2]);
+        assert_eq!(set.map_affine(0, 4).unwrap().data, vec![4..=4]);
+        assert_eq!(set.map_affine(i32::MAX / 4, 0), Err(AffineError::Overflow));
+        assert_eq!(RangeSet::from_vec(vec![0..=1 << 21]).map_affine(2, 0), Err(AffineError::TooManyPoints));
+
+        let tripled = RangeSet::from_vec(vec![1..=2]).map_affine(3, 0).unwrap();
+        assert!(tripled.contains(3) && tripled.contains(6));
+        assert!(!tripled.contains(4) && !tripled.contains(5));
+
+        assert_eq!(set.clamp(2..6).data, vec![2..=2, 5..=5]);
+        assert_eq!(set.clamp(..).data, set.data);
+        assert_eq!(set.clamp(8..).data, vec![]);
+
+        let ids = RangeSet::from_vec(vec![u64::MAX - 2..=u64::MAX]);
+        assert_eq!(ids.shift(0), Some(ids.clone()));
+        assert_eq!(ids.shift(1), None);
+        assert_eq!(ids.map_affine(1, 0), Ok(ids.clone()));
+        assert_eq!(ids.shift(-3), Some(RangeSet::from_vec(vec![u64::MAX - 5..=u64::MAX - 3])));
+        assert_eq!(RangeSet::from_vec(vec![2u64..=4]).shift(-3), None);
+    }
+
+    #[test]
+    fn test_offset_pipeline() {
+        let seeds = RangeSet::from_vec(vec![79i64..=92, 55..=67]);
+
+        let mut seed_to_soil = RangeMap::new();
+        seed_to_soil.insert(98..=99, -48i64);
+        seed_to_soil.insert(50..=97, 2);
+        let soil = seed_to_soil.shift_set(&seeds).unwrap();
+        assert_eq!(soil.data, vec![57..=69, 81..=94]);
+
+        let mut soil_to_fertilizer = RangeMap::new();
+        soil_to_fertilizer.insert(15..=51, -15);
+        soil_to_fertilizer.insert(52..=53, -15);
+        soil_to_fertilizer.insert(0..=14, 39);
+        let fertilizer = soil_to_fertilizer.shift_set(&soil).unwrap();
+        assert_eq!(fertilizer, soil);
+
+        let mut splitting = RangeMap::new();
+        splitting.insert(60..=85, 100);
+        assert_eq!(splitting.shift_set(&soil).unwrap().data, vec![57..=59, 86..=94, 160..=169, 181..=185]);
+
+        let mut overflowing = RangeMap::new();
+        overflowing.insert(90.., i64::MAX);
+        assert_eq!(overflowing.shift_set(&soil), None);
+
+        // unsigned ids move down through signed offsets
+        let ids = RangeSet::from_vec(vec![10u64..=20]);
+        let mut renumber = RangeMap::new();
+        renumber.insert(15..=20, -15i64);
+        assert_eq!(renumber.shift_set(&ids).unwrap().data, vec![0..=5, 10..=14]);
+        renumber.insert(10..=10, -11);
+        assert_eq!(renumber.shift_set(&ids), None);
+    }
+}
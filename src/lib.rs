@@ -17,20 +17,138 @@ pub mod harness {
 }
 
 pub mod util {
-    pub fn posmod(val: isize, k: isize) -> isize {
-        ((val % k) + k) % k
+    use std::ops::Div;
+    use crate::algebra::algebra::{strict_add, strict_mul, Integer, Semiring};
+
+    /// `val` mod `k` with the sign of `k`, so in `[0, k)` for positive `k`
+    pub fn posmod<T: Integer + Copy>(val: T, k: T) -> T {
+        let r = val % k;
+        if r != T::zero() && (r < T::zero()) != (k < T::zero()) { r + k } else { r }
+    }
+
+    /// `posmod`, or `None` for `k == 0` and the `MIN % -1` overflow
+    pub fn checked_posmod<T: Integer + Copy>(val: T, k: T) -> Option<T> {
+        let r = val.checked_rem(k)?;
+        Some(if r != T::zero() && (r < T::zero()) != (k < T::zero()) { r + k } else { r })
     }
 
     /// triangle number, sum of [1,k]
-    pub fn triangle(k: u64) -> u64 {
-        (k * (k + 1)) / 2
+    pub fn triangle<T>(k: T) -> T
+    where T: Semiring + Clone + Div<Output = T>
+    {
+        strict_mul(k.clone(), strict_add(k, T::one())) / (T::one() + T::one())
+    }
+
+    /// `triangle`, or `None` if the result doesn't fit. Halves the even factor first,
+    /// so only results that are out of range themselves fail.
+    pub fn checked_triangle<T: Integer + Copy>(k: T) -> Option<T> {
+        let two = T::one() + T::one();
+        let next = k.checked_add(T::one())?;
+        if k % two == T::zero() {
+            (k / two).checked_mul(next)
+        } else {
+            k.checked_mul(next / two)
+        }
     }
 
-    pub fn gcd(a: u32, b: u32) -> u32 {
-        if b == 0 { return a; }
+    pub fn gcd<T: Integer + Copy>(a: T, b: T) -> T {
+        if b == T::zero() { return a; }
         gcd(b, a % b)
     }
 
+    /// Binary searches over sorted slices and monotone predicates. Everything works on
+    /// half-open `[lo, hi)` intervals and returns the first position where a predicate flips.
+    pub mod search {
+        use crate::algebra::algebra::Integer;
+
+        /// Index of the first element `pred` is false for, given that it holds for a prefix of `data`
+        pub fn partition_point<T>(data: &[T], mut pred: impl FnMut(&T) -> bool) -> usize {
+            let (mut lo, mut hi) = (0, data.len());
+            while lo < hi {
+                let mid = lo + (hi - lo) / 2;
+                if pred(&data[mid]) { lo = mid + 1 } else { hi = mid }
+            }
+
+            lo
+        }
+
+        /// index of the first element whose key is `>= key`
+        pub fn lower_bound_by_key<T, K: Ord>(data: &[T], key: &K, mut f: impl FnMut(&T) -> K) -> usize {
+            partition_point(data, |x| f(x) < *key)
+        }
+
+        /// index of the first element whose key is `> key`
+        pub fn upper_bound_by_key<T, K: Ord>(data: &[T], key: &K, mut f: impl FnMut(&T) -> K) -> usize {
+            partition_point(data, |x| f(x) <= *key)
+        }
+
+        /// `partition_point` that probes indices 1, 2, 4, ... first, so it takes O(log k)
+        /// steps for an answer at index k. Good for walking forward through sorted queries.
+        pub fn exponential_search<T>(data: &[T], mut pred: impl FnMut(&T) -> bool) -> usize {
+            let mut bound = 1;
+            while bound <= data.len() && pred(&data[bound - 1]) {
+                bound *= 2;
+            }
+            let lo = bound / 2;
+            let hi = bound.min(data.len());
+
+            lo + partition_point(&data[lo..hi], pred)
+        }
+
+        /// Smallest `x` in `[lo, hi)` that `pred` holds for, or `hi` if there is none,
+        /// given that `pred` is false up to some point and true from there on. `hi - lo` has to fit into `T`.
+        pub fn bisect_int<T: Integer + Copy>(mut lo: T, mut hi: T, mut pred: impl FnMut(T) -> bool) -> T {
+            let two = T::one() + T::one();
+            while lo < hi {
+                let mid = lo + (hi - lo) / two;
+                if pred(mid) { hi = mid } else { lo = mid + T::one() }
+            }
+
+            lo
+        }
+
+        /// Narrows `[lo, hi]` down to the point where the monotone `pred` turns true,
+        /// until the interval is no wider than `eps`. Returns the upper end, where `pred` holds.
+        pub fn bisect_float(mut lo: f64, mut hi: f64, eps: f64, mut pred: impl FnMut(f64) -> bool) -> f64 {
+            // a fixed cap, as an `eps` below the float spacing would never be reached
+            for _ in 0..200 {
+                if hi - lo <= eps {
+                    break;
+                }
+                let mid = lo + (hi - lo) / 2.0;
+                if pred(mid) { hi = mid } else { lo = mid }
+            }
+
+            hi
+        }
+
+        #[test]
+        fn test_bounds_against_linear_scan() {
+            let data = [1, 3, 3, 3, 7, 9, 9, 12];
+            for key in 0..14 {
+                let lower = data.iter().position(|&x| x >= key).unwrap_or(data.len());
+                let upper = data.iter().position(|&x| x > key).unwrap_or(data.len());
+                assert_eq!(lower_bound_by_key(&data, &key, |&x| x), lower);
+                assert_eq!(upper_bound_by_key(&data, &key, |&x| x), upper);
+                assert_eq!(exponential_search(&data, |&x| x < key), lower);
+                assert_eq!(partition_point(&data, |&x| x <= key), data.partition_point(|&x| x <= key));
+            }
+            assert_eq!(lower_bound_by_key(&[] as &[i32], &5, |&x| x), 0);
+            assert_eq!(exponential_search(&[] as &[i32], |&x| x < 5), 0);
+        }
+
+        #[test]
+        fn test_bisect() {
+            assert_eq!(bisect_int(0u64, 1 << 31, |x| x * x >= 1_000_000_007), 31623);
+            assert_eq!(bisect_int(-100i32, 100, |x| x >= -37), -37);
+            assert_eq!(bisect_int(0u8, 10, |_| false), 10);
+            assert_eq!(bisect_int(i64::MIN / 2, i64::MAX / 2, |x| x >= 0), 0);
+
+            let root = bisect_float(0.0, 2.0, 1e-12, |x| x * x >= 2.0);
+            assert!((root - 2f64.sqrt()).abs() < 1e-11);
+        }
+    }
+
     pub fn transpose<T: Copy>(v: &Vec<Vec<T>>) -> Vec<Vec<T>> {
         if v.len() == 0 { return Vec::new(); }
         let mut r = Vec::with_capacity(v[0].len());
@@ -51,5 +169,38 @@ pub mod util {
         let v = vec![vec![1], vec![2, 3], vec![1,2,3]];
         assert_eq!(transpose(&v), vec![vec![1,2,1], vec![3, 2], vec![3]])
     }
-}
 
+    #[test]
+    pub fn test_generic_helpers() {
+        assert_eq!(gcd(12u32, 18), 6);
+        assert_eq!(gcd(-12i64, 18).abs(), 6);
+        assert_eq!(posmod(-7isize, 5), 3);
+        assert_eq!(posmod(-7i8, 5), 3);
+        assert_eq!(triangle(10u128), 55);
+    }
+
+    #[test]
+    #[cfg(feature = "strict-overflow")]
+    #[should_panic(expected = "with overflow")]
+    pub fn test_strict_overflow() {
+        // panics through the feature even where release builds would wrap
+        triangle(std::hint::black_box(u64::MAX));
+    }
+
+    #[test]
+    pub fn test_checked_helpers() {
+        assert_eq!(checked_posmod(-7i32, 5), Some(3));
+        assert_eq!(checked_posmod(7i32, -5), Some(-3));
+        assert_eq!(checked_posmod(3u8, 0), None);
+        assert_eq!(checked_posmod(i8::MIN, -1), None);
+        assert_eq!(posmod(i8::MAX - 1, i8::MAX), i8::MAX - 1);
+
+        assert_eq!(checked_triangle(10u8), Some(55));
+        assert_eq!(checked_triangle(-4i32), Some(6));
+        // 2^32 * (2^32 + 1) / 2 fits even though the full product doesn't
+        assert_eq!(checked_triangle(1u64 << 32), Some((1 << 31) * ((1 << 32) + 1)));
+        assert_eq!(checked_triangle(u64::MAX), None);
+        assert_eq!(checked_triangle(22u8), Some(253));
+        assert_eq!(checked_triangle(23u8), None);
+    }
+}